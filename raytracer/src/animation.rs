@@ -0,0 +1,117 @@
+use std::ops::{Add, Mul};
+
+use crate::Point3;
+
+/// A value keyed at points in time, linearly interpolated in between and held
+/// constant before the first and after the last key.
+#[derive(Clone)]
+pub struct Track<T> {
+    keys: Vec<(f64, T)>,
+}
+
+impl<T> Track<T>
+where
+    T: Copy + Add<Output = T> + Mul<f64, Output = T>,
+{
+    /// A track starting with the key `value` at `time`.
+    pub fn new(time: f64, value: T) -> Self {
+        Self {
+            keys: vec![(time, value)],
+        }
+    }
+
+    /// A value that never changes.
+    pub fn constant(value: T) -> Self {
+        Self::new(0., value)
+    }
+
+    /// Keys may be added in any order.
+    pub fn key(mut self, time: f64, value: T) -> Self {
+        let index = self.keys.partition_point(|&(t, _)| t <= time);
+        self.keys.insert(index, (time, value));
+        self
+    }
+
+    pub fn value(&self, time: f64) -> T {
+        let index = self.keys.partition_point(|&(t, _)| t <= time);
+        if index == 0 {
+            return self.keys[0].1;
+        }
+        if index == self.keys.len() {
+            return self.keys[index - 1].1;
+        }
+        let (t0, v0) = self.keys[index - 1];
+        let (t1, v1) = self.keys[index];
+        let s = (time - t0) / (t1 - t0);
+        v0 * (1. - s) + v1 * s
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.keys.len() == 1
+    }
+
+    /// Every key time strictly inside (`time0`, `time1`); together with the
+    /// end points these are where a linearly animated value reaches its extremes.
+    pub fn key_times(&self, time0: f64, time1: f64) -> Vec<f64> {
+        self.keys
+            .iter()
+            .map(|&(t, _)| t)
+            .filter(|&t| t > time0 && t < time1)
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct CameraPath {
+    pub lookfrom: Track<Point3>,
+    pub lookat: Track<Point3>,
+}
+
+impl CameraPath {
+    pub fn new(lookfrom: Track<Point3>, lookat: Track<Point3>) -> Self {
+        Self { lookfrom, lookat }
+    }
+}
+
+/// Timing of an image sequence. Frame `n` starts at `n / fps`.
+pub struct Animation {
+    pub frame_count: usize,
+    pub fps: f64,
+    pub shutter_angle: f64, // in degrees, 360 keeps the shutter open the whole frame
+}
+
+impl Animation {
+    pub fn new(frame_count: usize, fps: f64, shutter_angle: f64) -> Self {
+        Self {
+            frame_count,
+            fps,
+            shutter_angle,
+        }
+    }
+
+    /// When the shutter opens and closes for the given frame.
+    pub fn shutter_interval(&self, frame: usize) -> (f64, f64) {
+        let open = frame as f64 / self.fps;
+        (open, open + self.shutter_angle / 360. / self.fps)
+    }
+
+    #[allow(dead_code)]
+    pub fn duration(&self) -> f64 {
+        self.frame_count as f64 / self.fps
+    }
+}
+
+#[test]
+fn test_track() {
+    let track = Track::new(0., 0.).key(2., 4.).key(1., 1.);
+    assert_eq!(track.value(-1.), 0.);
+    assert_eq!(track.value(0.5), 0.5);
+    assert_eq!(track.value(1.5), 2.5);
+    assert_eq!(track.value(3.), 4.);
+    assert_eq!(track.key_times(0., 2.), vec![1.]);
+
+    // held at the first key until then, rather than from a value at 0
+    let late = Track::new(1., 3.).key(2., 5.);
+    assert_eq!(late.value(0.), 3.);
+    assert_eq!(late.value(1.5), 4.);
+}
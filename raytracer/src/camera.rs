@@ -1,70 +1,36 @@
-use crate::{
-    ray::Ray,
-    rtweekend::{degrees_to_radius, random_double},
-    Point3, Vec3,
-};
+pub mod equirectangular;
+pub mod fisheye;
+pub mod lens;
+pub mod orthographic;
+pub mod perspective;
+pub mod shutter;
 
-#[derive(Clone)]
-pub struct Camera {
-    origin: Point3,
-    lower_left_corner: Point3,
-    horizontal: Vec3,
-    vertical: Vec3,
-    u: Vec3,
-    v: Vec3,
-    _w: Vec3,
-    lens_radius: f64,
-    time0: f64,
-    time1: f64,
-}
+use crate::{ray::Ray, Point3, Vec3};
 
-impl Camera {
-    #[allow(clippy::too_many_arguments)]
-    pub fn new(
-        lookfrom: Point3,
-        lookat: Point3,
-        vup: Vec3,
-        vfov: f64, // vertical field-of-view in degrees
-        aspect_ratio: f64,
-        aperture: f64,
-        focus_dist: f64,
-        time0: f64,
-        time1: f64,
-    ) -> Self {
-        let theta = degrees_to_radius(vfov);
-        let h = (theta / 2.).tan();
-        let viewport_height: f64 = 2.0 * h;
-        let viewport_width: f64 = aspect_ratio * viewport_height;
+/// Where a point in the scene shows up on the film.
+pub struct Projection {
+    pub s: f64,
+    pub t: f64,
+    pub origin: Point3, // the point on the lens that sees it
+    pub pdf_dir: f64,   // solid angle density of `get_ray` sampling that direction
+}
 
-        let w = (lookfrom - lookat).unit();
-        let u = Vec3::cross(vup, w);
-        let v = Vec3::cross(w, u);
+pub trait Camera {
+    /// The ray through the image position (`s`, `t`) in [0, 1]², or `None` when
+    /// that position lies outside the area the projection covers.
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray>;
 
-        let horizontal = focus_dist * viewport_width * u;
-        let vertical = focus_dist * viewport_height * v;
-        let lower_left_corner = lookfrom - horizontal / 2.0 - vertical / 2.0 - w * focus_dist;
-        let lens_radius = aperture / 2.;
-        Self {
-            origin: lookfrom,
-            horizontal,
-            vertical,
-            lower_left_corner,
-            u,
-            v,
-            _w: w,
-            lens_radius,
-            time0,
-            time1,
-        }
+    /// Lets light paths connect to the camera. `None` if `p` is out of view, or
+    /// always for cameras that don't support it.
+    fn project(&self, _p: &Point3) -> Option<Projection> {
+        None
     }
+}
 
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * Vec3::random_in_unit_disk();
-        let offset = self.u * rd.x() + self.v * rd.y();
-        Ray::new(
-            self.origin + offset,
-            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
-            random_double(self.time0, self.time1),
-        )
-    }
+/// Right-handed camera frame: `w` points backwards, `u` right and `v` up.
+fn look_at_basis(lookfrom: Point3, lookat: Point3, vup: Vec3) -> (Vec3, Vec3, Vec3) {
+    let w = (lookfrom - lookat).unit();
+    let u = Vec3::cross(vup, w).unit();
+    let v = Vec3::cross(w, u);
+    (u, v, w)
 }
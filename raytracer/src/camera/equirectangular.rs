@@ -0,0 +1,97 @@
+use std::f64::consts::PI;
+
+use crate::{ray::Ray, Point3, Vec3};
+
+use super::{
+    look_at_basis,
+    shutter::{Shutter, ShutterCurve},
+    Camera,
+};
+
+/// Full 360° x 180° panorama. The image should have a 2:1 aspect ratio, or
+/// 1:1 in stereo mode where the left eye fills the top half and the right
+/// eye the bottom half (the usual over-under layout for VR players).
+#[derive(Clone)]
+pub struct EquirectangularCamera {
+    origin: Point3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    eye_separation: Option<f64>,
+    shutter: Shutter,
+}
+
+impl EquirectangularCamera {
+    /// `lookat` ends up in the middle of the panorama.
+    pub fn new(lookfrom: Point3, lookat: Point3, vup: Vec3, time0: f64, time1: f64) -> Self {
+        let (u, v, w) = look_at_basis(lookfrom, lookat, vup);
+        Self {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            eye_separation: None,
+            shutter: Shutter::new(time0, time1),
+        }
+    }
+
+    /// Omni-directional stereo with the given interpupillary distance in scene units.
+    pub fn new_stereo(
+        lookfrom: Point3,
+        lookat: Point3,
+        vup: Vec3,
+        eye_separation: f64,
+        time0: f64,
+        time1: f64,
+    ) -> Self {
+        Self {
+            eye_separation: Some(eye_separation),
+            ..Self::new(lookfrom, lookat, vup, time0, time1)
+        }
+    }
+
+    /// Shapes the exposure from `time0` to `time1` given to the constructor.
+    pub fn with_shutter(mut self, curve: ShutterCurve, readout: f64) -> Self {
+        self.shutter = self.shutter.with_curve(curve, readout);
+        self
+    }
+}
+
+impl Camera for EquirectangularCamera {
+    fn get_ray(&self, s: f64, mut t: f64) -> Option<Ray> {
+        let mut origin = self.origin;
+        let time = self.shutter.sample(t);
+        let phi = (s - 0.5) * 2. * PI;
+
+        if let Some(separation) = self.eye_separation {
+            let left_eye = t >= 0.5;
+            t = if left_eye { 2. * t - 1. } else { 2. * t };
+            // each eye sits on a circle, offset along the tangent of the view direction
+            let tangent = phi.cos() * self.u + phi.sin() * self.w;
+            let half = if left_eye { -0.5 } else { 0.5 } * separation;
+            origin += half * tangent;
+        }
+
+        let theta = (t - 0.5) * PI;
+        let direction =
+            theta.cos() * (phi.sin() * self.u - phi.cos() * self.w) + theta.sin() * self.v;
+        Some(Ray::new(origin, direction, time))
+    }
+}
+
+#[test]
+fn test_panorama_center() {
+    let cam = EquirectangularCamera::new(
+        Point3::zero(),
+        Point3::new(0., 0., -5.),
+        Vec3::new(0., 1., 0.),
+        0.,
+        0.,
+    );
+    let center = cam.get_ray(0.5, 0.5).unwrap();
+    assert!((center.direction() - Vec3::new(0., 0., -1.)).length() < 1e-12);
+    let right = cam.get_ray(0.75, 0.5).unwrap();
+    assert!((right.direction() - Vec3::new(1., 0., 0.)).length() < 1e-12);
+    let up = cam.get_ray(0.3, 1.).unwrap();
+    assert!((up.direction() - Vec3::new(0., 1., 0.)).length() < 1e-12);
+}
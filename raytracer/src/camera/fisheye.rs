@@ -0,0 +1,72 @@
+use crate::{ray::Ray, rtweekend::degrees_to_radius, Point3, Vec3};
+
+use super::{
+    look_at_basis,
+    shutter::{Shutter, ShutterCurve},
+    Camera,
+};
+
+/// Equidistant fisheye: the angle from the view axis grows linearly with the
+/// distance from the image center. The image circle touches the shorter side.
+#[derive(Clone)]
+pub struct FisheyeCamera {
+    origin: Point3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    half_fov: f64,
+    aspect_ratio: f64,
+    shutter: Shutter,
+}
+
+impl FisheyeCamera {
+    pub fn new(
+        lookfrom: Point3,
+        lookat: Point3,
+        vup: Vec3,
+        fov: f64, // field-of-view across the image circle in degrees, up to 360
+        aspect_ratio: f64,
+        time0: f64,
+        time1: f64,
+    ) -> Self {
+        let (u, v, w) = look_at_basis(lookfrom, lookat, vup);
+        Self {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            half_fov: degrees_to_radius(fov) / 2.,
+            aspect_ratio,
+            shutter: Shutter::new(time0, time1),
+        }
+    }
+
+    /// Shapes the exposure from `time0` to `time1` given to the constructor.
+    pub fn with_shutter(mut self, curve: ShutterCurve, readout: f64) -> Self {
+        self.shutter = self.shutter.with_curve(curve, readout);
+        self
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let mut x = 2. * s - 1.;
+        let mut y = 2. * t - 1.;
+        if self.aspect_ratio > 1. {
+            x *= self.aspect_ratio;
+        } else {
+            y /= self.aspect_ratio;
+        }
+
+        let r = (x * x + y * y).sqrt();
+        if r > 1. {
+            return None;
+        }
+
+        let theta = r * self.half_fov;
+        let phi = y.atan2(x);
+        let direction =
+            theta.sin() * (phi.cos() * self.u + phi.sin() * self.v) - theta.cos() * self.w;
+        Some(Ray::new(self.origin, direction, self.shutter.sample(t)))
+    }
+}
@@ -0,0 +1,137 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+    material::texture::Texture,
+    rtweekend::{random_double_2d, random_double_unit, random_int},
+    Point3, Vec3,
+};
+
+/// A camera described the way a photographer would, with lengths in millimetres.
+pub struct Lens {
+    focal_length: f64,
+    f_stop: f64,
+    sensor_width: f64,
+    sensor_height: f64,
+    mm_per_unit: f64, // how many millimetres one scene unit stands for
+}
+
+impl Lens {
+    pub fn new(
+        focal_length: f64,
+        f_stop: f64,
+        sensor_width: f64,
+        sensor_height: f64,
+        mm_per_unit: f64,
+    ) -> Self {
+        Self {
+            focal_length,
+            f_stop,
+            sensor_width,
+            sensor_height,
+            mm_per_unit,
+        }
+    }
+
+    /// Vertical field of view in degrees once the sensor is cropped to the image.
+    pub fn vfov(&self, aspect_ratio: f64) -> f64 {
+        let height = self.sensor_height.min(self.sensor_width / aspect_ratio);
+        2. * (height / (2. * self.focal_length)).atan() * 180. / PI
+    }
+
+    /// Aperture diameter in scene units.
+    pub fn aperture(&self) -> f64 {
+        self.focal_length / self.f_stop / self.mm_per_unit
+    }
+}
+
+/// The blades of a polygonal aperture, made with `ApertureShape::polygon`.
+#[derive(Clone, Copy)]
+pub struct Blades {
+    count: usize,
+    rotation: f64,
+}
+
+/// The shape of the lens opening, which is what out-of-focus highlights take on.
+#[derive(Clone)]
+pub enum ApertureShape {
+    Circle,
+    Polygon(Blades),
+    // the mask's unit square is inscribed in the unit disk, corners on the rim
+    #[allow(dead_code)]
+    Texture(Arc<dyn Texture + Send + Sync>),
+}
+
+impl ApertureShape {
+    /// A regular polygon with corners on the unit circle, the first at
+    /// `rotation` radians.
+    pub fn polygon(blades: usize, rotation: f64) -> Self {
+        assert!(blades >= 3, "an aperture needs at least 3 blades");
+        ApertureShape::Polygon(Blades {
+            count: blades,
+            rotation,
+        })
+    }
+
+    /// A random point on the aperture, scaled to fit the unit disk.
+    pub fn sample(&self) -> Vec3 {
+        match self {
+            ApertureShape::Circle => Vec3::random_in_unit_disk(),
+            ApertureShape::Polygon(Blades { count, rotation }) => {
+                let step = 2. * PI / *count as f64;
+                let k = random_int(0, *count as isize - 1) as f64;
+                let a = Vec3::new((rotation + k * step).cos(), (rotation + k * step).sin(), 0.);
+                let b = Vec3::new(
+                    (rotation + (k + 1.) * step).cos(),
+                    (rotation + (k + 1.) * step).sin(),
+                    0.,
+                );
+                // uniform point in the triangle (center, a, b)
+                let (mut s, mut t) = random_double_2d();
+                if s + t > 1. {
+                    s = 1. - s;
+                    t = 1. - t;
+                }
+                s * a + t * b
+            }
+            ApertureShape::Texture(mask) => {
+                // rejection sampling against the mask brightness, giving up on empty masks
+                for _ in 0..64 {
+                    let (u, v) = random_double_2d();
+                    let transmit = mask.value(u, v, &Point3::zero());
+                    let weight = (transmit.x() + transmit.y() + transmit.z()) / 3.;
+                    if random_double_unit() < weight {
+                        // shrunk so the square's corners land on the rim
+                        return Vec3::new(2. * u - 1., 2. * v - 1., 0.) / 2_f64.sqrt();
+                    }
+                }
+                Vec3::zero()
+            }
+        }
+    }
+}
+
+#[test]
+fn test_aperture_in_unit_disk() {
+    let hexagon = ApertureShape::polygon(6, 0.3);
+    let mask = ApertureShape::Texture(Arc::new(crate::material::texture::SolidColor::new(
+        crate::Color3::ones(),
+    )));
+    for _ in 0..1000 {
+        assert!(hexagon.sample().length() <= 1.);
+        assert!(mask.sample().length() <= 1.);
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_aperture_needs_blades() {
+    ApertureShape::polygon(2, 0.);
+}
+
+#[test]
+fn test_lens_fov() {
+    let lens = Lens::new(50., 2., 36., 24., 1000.);
+    let vfov = lens.vfov(1.5);
+    assert!((vfov - 26.991).abs() < 1e-3);
+    assert_eq!(lens.aperture(), 0.025);
+}
@@ -0,0 +1,56 @@
+use crate::{ray::Ray, Point3, Vec3};
+
+use super::{
+    look_at_basis,
+    shutter::{Shutter, ShutterCurve},
+    Camera,
+};
+
+/// Parallel projection, e.g. for architectural elevations.
+#[derive(Clone)]
+pub struct OrthographicCamera {
+    lower_left_corner: Point3,
+    horizontal: Vec3,
+    vertical: Vec3,
+    direction: Vec3,
+    shutter: Shutter,
+}
+
+impl OrthographicCamera {
+    pub fn new(
+        lookfrom: Point3,
+        lookat: Point3,
+        vup: Vec3,
+        view_height: f64, // height of the visible area in scene units
+        aspect_ratio: f64,
+        time0: f64,
+        time1: f64,
+    ) -> Self {
+        let (u, v, w) = look_at_basis(lookfrom, lookat, vup);
+        let horizontal = aspect_ratio * view_height * u;
+        let vertical = view_height * v;
+        Self {
+            lower_left_corner: lookfrom - horizontal / 2. - vertical / 2.,
+            horizontal,
+            vertical,
+            direction: -w,
+            shutter: Shutter::new(time0, time1),
+        }
+    }
+
+    /// Shapes the exposure from `time0` to `time1` given to the constructor.
+    pub fn with_shutter(mut self, curve: ShutterCurve, readout: f64) -> Self {
+        self.shutter = self.shutter.with_curve(curve, readout);
+        self
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        Some(Ray::new(
+            self.lower_left_corner + s * self.horizontal + t * self.vertical,
+            self.direction,
+            self.shutter.sample(t),
+        ))
+    }
+}
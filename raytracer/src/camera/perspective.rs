@@ -0,0 +1,136 @@
+use crate::{ray::Ray, rtweekend::degrees_to_radius, Point3, Vec3};
+
+use super::{
+    lens::ApertureShape,
+    shutter::{Shutter, ShutterCurve},
+    Camera, Projection,
+};
+
+#[derive(Clone)]
+pub struct PerspectiveCamera {
+    origin: Point3,
+    lower_left_corner: Point3,
+    horizontal: Vec3,
+    vertical: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    lens_radius: f64,
+    aperture_shape: ApertureShape,
+    shutter: Shutter,
+}
+
+impl PerspectiveCamera {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        lookfrom: Point3,
+        lookat: Point3,
+        vup: Vec3,
+        vfov: f64, // vertical field-of-view in degrees
+        aspect_ratio: f64,
+        aperture: f64,
+        focus_dist: f64,
+        time0: f64,
+        time1: f64,
+    ) -> Self {
+        let theta = degrees_to_radius(vfov);
+        let h = (theta / 2.).tan();
+        let viewport_height: f64 = 2.0 * h;
+        let viewport_width: f64 = aspect_ratio * viewport_height;
+
+        let w = (lookfrom - lookat).unit();
+        let u = Vec3::cross(vup, w);
+        let v = Vec3::cross(w, u);
+
+        let horizontal = focus_dist * viewport_width * u;
+        let vertical = focus_dist * viewport_height * v;
+        let lower_left_corner = lookfrom - horizontal / 2.0 - vertical / 2.0 - w * focus_dist;
+        let lens_radius = aperture / 2.;
+        Self {
+            origin: lookfrom,
+            horizontal,
+            vertical,
+            lower_left_corner,
+            u,
+            v,
+            w,
+            lens_radius,
+            aperture_shape: ApertureShape::Circle,
+            shutter: Shutter::new(time0, time1),
+        }
+    }
+
+    pub fn with_aperture(mut self, aperture_shape: ApertureShape) -> Self {
+        self.aperture_shape = aperture_shape;
+        self
+    }
+
+    /// Shapes the exposure from `time0` to `time1` given to the constructor.
+    pub fn with_shutter(mut self, curve: ShutterCurve, readout: f64) -> Self {
+        self.shutter = self.shutter.with_curve(curve, readout);
+        self
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let rd = self.lens_radius * self.aperture_shape.sample();
+        let offset = self.u * rd.x() + self.v * rd.y();
+        Some(Ray::new(
+            self.origin + offset,
+            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
+            self.shutter.sample(t),
+        ))
+    }
+
+    /// Only pinholes can be connected to, as a lens would need its own sample.
+    fn project(&self, p: &Point3) -> Option<Projection> {
+        if self.lens_radius > 0. {
+            return None;
+        }
+        let forward = -self.w;
+        let d = *p - self.origin;
+        let depth = d * forward;
+        if depth <= 0. {
+            return None;
+        }
+
+        let center = self.lower_left_corner + self.horizontal / 2. + self.vertical / 2.;
+        let focus_dist = (center - self.origin) * forward;
+        let on_plane = self.origin + d * (focus_dist / depth) - self.lower_left_corner;
+        let s = on_plane * self.horizontal / self.horizontal.squared_length();
+        let t = on_plane * self.vertical / self.vertical.squared_length();
+        if !(0. ..1.).contains(&s) || !(0. ..1.).contains(&t) {
+            return None;
+        }
+
+        // image plane area at unit distance
+        let area = self.horizontal.length() * self.vertical.length() / (focus_dist * focus_dist);
+        let cos_theta = depth / d.length();
+        Some(Projection {
+            s,
+            t,
+            origin: self.origin,
+            pdf_dir: 1. / (area * cos_theta.powi(3)),
+        })
+    }
+}
+
+#[test]
+fn test_project_roundtrip() {
+    let cam = PerspectiveCamera::new(
+        Point3::new(1., 2., 3.),
+        Point3::zero(),
+        Vec3::new(0., 1., 0.),
+        40.,
+        1.5,
+        0.,
+        4.,
+        0.,
+        1.,
+    );
+    let r = cam.get_ray(0.3, 0.8).unwrap();
+    let projection = cam.project(&r.at(7.)).unwrap();
+    assert!((projection.s - 0.3).abs() < 1e-9);
+    assert!((projection.t - 0.8).abs() < 1e-9);
+}
@@ -0,0 +1,111 @@
+use crate::rtweekend::random_double_unit;
+
+/// How far open the shutter is over the course of one exposure.
+#[derive(Clone)]
+pub enum ShutterCurve {
+    Box,
+    Triangle,
+    /// Openness of equally long slices of the exposure, e.g. from a measured shutter.
+    #[allow(dead_code)]
+    Custom(Vec<f64>),
+}
+
+#[derive(Clone)]
+pub struct Shutter {
+    open: f64,
+    close: f64,
+    curve: ShutterCurve,
+    cdf: Vec<f64>,
+    readout: f64,
+}
+
+impl Shutter {
+    /// A global shutter that is fully open from `open` to `close`.
+    pub fn new(open: f64, close: f64) -> Self {
+        Self::new_curve(open, close, ShutterCurve::Box, 0.)
+    }
+
+    /// `readout` is the time a rolling shutter takes to sweep from the top row
+    /// to the bottom row; every row is exposed for `close - open - readout`.
+    pub fn new_curve(open: f64, close: f64, curve: ShutterCurve, readout: f64) -> Self {
+        let mut cdf = Vec::new();
+        if let ShutterCurve::Custom(openness) = &curve {
+            let mut sum = 0.;
+            for weight in openness {
+                sum += weight.max(0.);
+                cdf.push(sum);
+            }
+            if sum <= 0. {
+                panic!("Shutter curve never opens!");
+            }
+            for iter in &mut cdf {
+                *iter /= sum;
+            }
+        }
+        Self {
+            open,
+            close,
+            curve,
+            cdf,
+            readout: readout.clamp(0., close - open),
+        }
+    }
+
+    /// The same interval with a different curve and readout.
+    pub fn with_curve(&self, curve: ShutterCurve, readout: f64) -> Self {
+        Self::new_curve(self.open, self.close, curve, readout)
+    }
+
+    /// A random time for a sample at vertical image position `t` (1 is the top row).
+    pub fn sample(&self, t: f64) -> f64 {
+        let exposure = self.close - self.open - self.readout;
+        let row_open = self.open + (1. - t.clamp(0., 1.)) * self.readout;
+        row_open + self.sample_curve(random_double_unit()) * exposure
+    }
+
+    /// Maps a uniform number to a point in [0, 1] distributed like the shutter curve.
+    fn sample_curve(&self, u: f64) -> f64 {
+        match &self.curve {
+            ShutterCurve::Box => u,
+            ShutterCurve::Triangle => {
+                if u < 0.5 {
+                    (u / 2.).sqrt()
+                } else {
+                    1. - ((1. - u) / 2.).sqrt()
+                }
+            }
+            ShutterCurve::Custom(_) => {
+                let i = self
+                    .cdf
+                    .partition_point(|&c| c <= u)
+                    .min(self.cdf.len() - 1);
+                let lo = if i == 0 { 0. } else { self.cdf[i - 1] };
+                let within = if self.cdf[i] > lo {
+                    (u - lo) / (self.cdf[i] - lo)
+                } else {
+                    0.5
+                };
+                (i as f64 + within) / self.cdf.len() as f64
+            }
+        }
+    }
+}
+
+#[test]
+fn test_shutter_curves() {
+    let triangle = Shutter::new_curve(0., 1., ShutterCurve::Triangle, 0.);
+    assert_eq!(triangle.sample_curve(0.5), 0.5);
+    assert_eq!(triangle.sample_curve(0.125), 0.25);
+
+    // only the second half of the exposure lets light through
+    let custom = Shutter::new_curve(0., 1., ShutterCurve::Custom(vec![0., 1.]), 0.);
+    assert_eq!(custom.sample_curve(0.), 0.5);
+    assert_eq!(custom.sample_curve(0.5), 0.75);
+
+    // the top row is exposed first, the bottom row last
+    let rolling = Shutter::new_curve(0., 1., ShutterCurve::Box, 0.5);
+    for _ in 0..100 {
+        assert!(rolling.sample(1.) <= 0.5);
+        assert!(rolling.sample(0.) >= 0.5);
+    }
+}
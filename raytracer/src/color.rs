@@ -1,28 +1,33 @@
 use crate::{rtweekend::clamp, Color3};
 use image::RgbImage;
 
-/// the multi-sample write_color() function
-pub fn write_color(
-    mut color: Color3,
-    samples_per_pixel: usize,
-    img: &mut RgbImage,
-    i: usize,
-    j: usize,
-) {
+/// Relative luminance of a linear color.
+pub fn luminance(color: Color3) -> f64 {
+    0.2126 * color.x() + 0.7152 * color.y() + 0.0722 * color.z()
+}
+
+/// write_color() for an already reconstructed pixel value
+pub fn write_color(mut color: Color3, img: &mut RgbImage, i: usize, j: usize) {
     // Clear NaNs
-    for i in 0..2 {
+    for i in 0..3 {
         if color[i].is_nan() || color[i].is_infinite() {
             color[i] = 0.;
         }
     }
 
-    //Divide the color by the number of samples.
-    let scale = 1. / samples_per_pixel as f64;
-    color *= scale;
     let r = color.x().powf(0.5);
     let g = color.y().powf(0.5);
     let b = color.z().powf(0.5);
+    write_pixel(Color3::new(r, g, b), img, i, j);
+}
+
+/// Writes non-color data in [0, 1], such as normals or depth, without gamma.
+pub fn write_data(value: Color3, img: &mut RgbImage, i: usize, j: usize) {
+    write_pixel(value, img, i, j);
+}
 
+fn write_pixel(value: Color3, img: &mut RgbImage, i: usize, j: usize) {
+    let (r, g, b) = (value.x(), value.y(), value.z());
     let pixel_color = [
         (clamp(r, 0., 0.999) * 256.).floor() as u8,
         (clamp(g, 0., 0.999) * 255.).floor() as u8,
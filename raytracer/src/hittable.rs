@@ -1,13 +1,27 @@
 mod aabb;
 pub mod aarect;
+pub mod animated;
 pub mod boxes;
 pub mod bvh;
+pub mod cone;
 pub mod constant_medium;
+pub mod csg;
+pub mod curve;
+pub mod cylinder;
+pub mod disk;
 pub mod hittable_list;
+pub mod instance;
+pub mod mesh;
 pub mod moving_sphere;
+pub mod ply;
+pub mod quad;
+pub mod sdf;
 pub mod sphere;
+pub mod tlas;
+pub mod torus;
+pub mod triangle;
 
-use std::f64::INFINITY;
+use std::f64::{consts::PI, INFINITY};
 use std::sync::Arc;
 
 pub use crate::material::Material;
@@ -17,6 +31,7 @@ use crate::Ray;
 use crate::Vec3;
 use aabb::AABB;
 
+#[derive(Clone)]
 pub struct HitRecord {
     pub p: Point3,
     pub normal: Vec3,
@@ -25,6 +40,8 @@ pub struct HitRecord {
     pub v: f64,
     pub mat_ptr: Option<Arc<dyn Material + Send + Sync>>,
     pub front_face: bool,
+    pub object_id: usize, // 0 unless tagged by `ObjectId`
+    pub tangent: Vec3,    // along increasing u, for shapes that have a direction
 }
 
 impl HitRecord {
@@ -46,10 +63,59 @@ impl HitRecord {
             v: (0.),
             front_face: (false),
             mat_ptr: None,
+            object_id: 0,
+            tangent: Vec3::zero(),
         }
     }
 }
 
+/// A point on the surface of an area light, for light paths to start from.
+pub struct SurfaceSample {
+    pub p: Point3,
+    pub normal: Vec3,
+    pub pdf: f64, // per unit area
+}
+
+/// The solid angle density at `o` of direction `v` for an object whose light
+/// samples are spread evenly over `area`. Summed over every crossing of the
+/// ray, as a sample on any of them would have been seen along `v`.
+pub fn area_pdf_value(object: &dyn Hittable, area: f64, o: &Point3, v: &Vec3) -> f64 {
+    let r = Ray::new(*o, *v, 0.);
+    let eps = 0.001 / v.length();
+    let mut pdf = 0.;
+    let mut t_min = eps;
+    let mut rec = HitRecord::new();
+    while object.hit(&r, t_min, INFINITY, &mut rec) {
+        let distance_squared = rec.t * rec.t * v.squared_length();
+        let cosine = (*v * rec.normal / v.length()).abs();
+        pdf += distance_squared / (cosine * area);
+        t_min = rec.t + eps;
+    }
+    pdf
+}
+
+/// The direction from `o` to a point sampled on `object`'s surface.
+pub fn random_to_surface(object: &dyn Hittable, o: &Point3) -> Vec3 {
+    object.sample_surface().unwrap().p - *o
+}
+
+/// The area density of a surface sample at `o + v`, for an object whose
+/// samples are spread evenly over `area`. Zero unless the ray from `o`
+/// crosses the surface right there.
+pub fn uniform_surface_pdf(object: &dyn Hittable, area: f64, o: &Point3, v: &Vec3) -> f64 {
+    let mut rec = HitRecord::new();
+    if object.hit(&Ray::new(*o, *v, 0.), 1. - 1e-4, 1. + 1e-4, &mut rec) {
+        1. / area
+    } else {
+        0.
+    }
+}
+
+/// How far around the z axis `p` lies, from 0 to 1, for `u` on round shapes.
+pub fn azimuth(p: &Vec3) -> f64 {
+    (p.y().atan2(p.x()) + PI) / (2. * PI)
+}
+
 pub trait Hittable {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool;
     fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut AABB) -> bool;
@@ -59,6 +125,13 @@ pub trait Hittable {
     fn random(&self, _o: &Vec3) -> Vec3 {
         Vec3::new(1., 0., 0.)
     }
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        None
+    }
+    /// The density of `sample_surface` at `o + v`, which `o` sees along `v`.
+    fn surface_pdf(&self, _o: &Point3, _v: &Vec3) -> f64 {
+        0.0
+    }
 }
 
 pub struct Translate {
@@ -209,4 +282,54 @@ impl Hittable for FlipFace {
             true
         }
     }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        self.ptr.sample_surface()
+    }
+
+    fn surface_pdf(&self, o: &Point3, v: &Vec3) -> f64 {
+        self.ptr.surface_pdf(o, v)
+    }
+}
+
+/// Tags every hit on `ptr` with `id` for the object ID render pass.
+pub struct ObjectId {
+    ptr: Arc<dyn Hittable + Send + Sync>,
+    id: usize,
+}
+
+impl ObjectId {
+    pub fn new(ptr: Arc<dyn Hittable + Send + Sync>, id: usize) -> Self {
+        Self { ptr, id }
+    }
+}
+
+impl Hittable for ObjectId {
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut AABB) -> bool {
+        self.ptr.bounding_box(time0, time1, output_box)
+    }
+
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        if !self.ptr.hit(r, t_min, t_max, rec) {
+            return false;
+        }
+        rec.object_id = self.id;
+        true
+    }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        self.ptr.pdf_value(o, v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        self.ptr.random(o)
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        self.ptr.sample_surface()
+    }
+
+    fn surface_pdf(&self, o: &Point3, v: &Vec3) -> f64 {
+        self.ptr.surface_pdf(o, v)
+    }
 }
@@ -1,6 +1,6 @@
 use std::mem::swap;
 
-use crate::{ray::Ray, Point3};
+use crate::{ray::Ray, Point3, Vec3};
 
 #[derive(Clone, Debug, PartialEq, Copy)]
 #[allow(clippy::upper_case_acronyms)]
@@ -14,6 +14,27 @@ impl AABB {
         Self { minimum, maximum }
     }
 
+    /// The box around `points`, padded so that flat shapes still have volume.
+    pub fn from_points(points: &[Point3]) -> Self {
+        let mut minimum = Point3::ones() * f64::INFINITY;
+        let mut maximum = Point3::ones() * -f64::INFINITY;
+        for p in points {
+            for a in 0..3 {
+                minimum[a] = minimum[a].min(p[a]);
+                maximum[a] = maximum[a].max(p[a]);
+            }
+        }
+        let padding = Vec3::ones() * 0.0001;
+        Self::new(minimum - padding, maximum + padding)
+    }
+
+    /// The box around a circle of `radius` facing unit `normal`.
+    pub fn around_circle(center: Point3, normal: Vec3, radius: f64) -> Self {
+        let extent = |n: f64| radius * (1. - n * n).max(0.).sqrt();
+        let half = Vec3::new(extent(normal.x()), extent(normal.y()), extent(normal.z()));
+        Self::from_points(&[center - half, center + half])
+    }
+
     pub fn min(&self) -> Point3 {
         self.minimum
     }
@@ -39,6 +60,24 @@ impl AABB {
         true
     }
 
+    /// The part of `t_min..t_max` during which `r` is inside the box.
+    pub fn clip(&self, r: &Ray, mut t_min: f64, mut t_max: f64) -> Option<(f64, f64)> {
+        for a in 0..3 {
+            let inv_d = 1.0 / r.direction()[a];
+            let mut t0 = (self.minimum[a] - r.origin()[a]) * inv_d;
+            let mut t1 = (self.maximum[a] - r.origin()[a]) * inv_d;
+            if inv_d < 0.0_f64 {
+                swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max <= t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+
     pub fn surrounding_box(box0: &Self, box1: &Self) -> Self {
         let small = Point3::new(
             box0.min().x().min(box1.min().x()),
@@ -1,8 +1,8 @@
 use std::{f64::INFINITY, sync::Arc};
 
-use crate::{ray::Ray, rtweekend::random_double, Point3, Vec3};
+use crate::{ray::Ray, rtweekend::random_double_2d, Point3, Vec3};
 
-use super::{aabb::AABB, HitRecord, Hittable, Material};
+use super::{aabb::AABB, uniform_surface_pdf, HitRecord, Hittable, Material, SurfaceSample};
 
 pub struct XyRect {
     mp: Arc<dyn Material + Send + Sync>,
@@ -63,6 +63,21 @@ impl Hittable for XyRect {
         rec.p = r.at(t);
         true
     }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let (s, t) = random_double_2d();
+        let x = self.x0 + s * (self.x1 - self.x0);
+        let y = self.y0 + t * (self.y1 - self.y0);
+        Some(SurfaceSample {
+            p: Point3::new(x, y, self.k),
+            normal: Vec3::new(0., 0., 1.),
+            pdf: 1. / ((self.x1 - self.x0) * (self.y1 - self.y0)),
+        })
+    }
+
+    fn surface_pdf(&self, origin: &Point3, v: &Vec3) -> f64 {
+        uniform_surface_pdf(self, (self.x1 - self.x0) * (self.y1 - self.y0), origin, v)
+    }
 }
 
 pub struct XzRect {
@@ -139,13 +154,29 @@ impl Hittable for XzRect {
     }
 
     fn random(&self, origin: &Vec3) -> Vec3 {
+        let (s, t) = random_double_2d();
         let random_point = Point3::new(
-            random_double(self.x0, self.x1),
+            self.x0 + s * (self.x1 - self.x0),
             self.k,
-            random_double(self.z0, self.z1),
+            self.z0 + t * (self.z1 - self.z0),
         );
         random_point - *origin
     }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let (s, t) = random_double_2d();
+        let x = self.x0 + s * (self.x1 - self.x0);
+        let z = self.z0 + t * (self.z1 - self.z0);
+        Some(SurfaceSample {
+            p: Point3::new(x, self.k, z),
+            normal: Vec3::new(0., 1., 0.),
+            pdf: 1. / ((self.x1 - self.x0) * (self.z1 - self.z0)),
+        })
+    }
+
+    fn surface_pdf(&self, origin: &Point3, v: &Vec3) -> f64 {
+        uniform_surface_pdf(self, (self.x1 - self.x0) * (self.z1 - self.z0), origin, v)
+    }
 }
 
 pub struct YzRect {
@@ -207,4 +238,19 @@ impl Hittable for YzRect {
         rec.p = r.at(t);
         true
     }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let (s, t) = random_double_2d();
+        let y = self.y0 + s * (self.y1 - self.y0);
+        let z = self.z0 + t * (self.z1 - self.z0);
+        Some(SurfaceSample {
+            p: Point3::new(self.k, y, z),
+            normal: Vec3::new(1., 0., 0.),
+            pdf: 1. / ((self.y1 - self.y0) * (self.z1 - self.z0)),
+        })
+    }
+
+    fn surface_pdf(&self, origin: &Point3, v: &Vec3) -> f64 {
+        uniform_surface_pdf(self, (self.y1 - self.y0) * (self.z1 - self.z0), origin, v)
+    }
 }
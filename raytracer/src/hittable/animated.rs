@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use crate::{animation::Track, ray::Ray, rtweekend::degrees_to_radius, Point3, Vec3};
+
+use super::{aabb::AABB, HitRecord, Hittable};
+
+/// An object moved by keyframed translation and rotation around the Y axis,
+/// both evaluated at the time of each ray so that motion blur comes for free.
+pub struct Animated {
+    ptr: Arc<dyn Hittable + Send + Sync>,
+    translation: Track<Vec3>,
+    rotation: Track<f64>, // degrees around the Y axis, applied before translation
+}
+
+impl Animated {
+    pub fn new(
+        ptr: Arc<dyn Hittable + Send + Sync>,
+        translation: Track<Vec3>,
+        rotation: Track<f64>,
+    ) -> Self {
+        Self {
+            ptr,
+            translation,
+            rotation,
+        }
+    }
+
+    fn rotate(v: Vec3, sin_theta: f64, cos_theta: f64) -> Vec3 {
+        Vec3::new(
+            cos_theta * v.x() + sin_theta * v.z(),
+            v.y(),
+            -sin_theta * v.x() + cos_theta * v.z(),
+        )
+    }
+}
+
+impl Hittable for Animated {
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut AABB) -> bool {
+        let mut local = AABB::new(Point3::zero(), Point3::zero());
+        if !self.ptr.bounding_box(time0, time1, &mut local) {
+            return false;
+        }
+
+        // a spinning object can point anywhere, so bound it by a cylinder around Y
+        let (min, max) = if self.rotation.is_constant() {
+            let radians = degrees_to_radius(self.rotation.value(0.));
+            let mut min = f64::INFINITY * Point3::ones();
+            let mut max = -f64::INFINITY * Point3::ones();
+            for i in 0..8 {
+                let corner = Point3::new(
+                    if i & 1 == 0 {
+                        local.min().x()
+                    } else {
+                        local.max().x()
+                    },
+                    if i & 2 == 0 {
+                        local.min().y()
+                    } else {
+                        local.max().y()
+                    },
+                    if i & 4 == 0 {
+                        local.min().z()
+                    } else {
+                        local.max().z()
+                    },
+                );
+                let rotated = Self::rotate(corner, radians.sin(), radians.cos());
+                for c in 0..3 {
+                    min[c] = min[c].min(rotated[c]);
+                    max[c] = max[c].max(rotated[c]);
+                }
+            }
+            (min, max)
+        } else {
+            let mut radius: f64 = 0.;
+            for x in [local.min().x(), local.max().x()] {
+                for z in [local.min().z(), local.max().z()] {
+                    radius = radius.max((x * x + z * z).sqrt());
+                }
+            }
+            (
+                Point3::new(-radius, local.min().y(), -radius),
+                Point3::new(radius, local.max().y(), radius),
+            )
+        };
+
+        let mut times = self.translation.key_times(time0, time1);
+        times.push(time0);
+        times.push(time1);
+        let mut first = true;
+        for time in times {
+            let offset = self.translation.value(time);
+            let moved = AABB::new(min + offset, max + offset);
+            *output_box = if first {
+                moved
+            } else {
+                AABB::surrounding_box(output_box, &moved)
+            };
+            first = false;
+        }
+        true
+    }
+
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let offset = self.translation.value(r.time());
+        let radians = degrees_to_radius(self.rotation.value(r.time()));
+        let (sin_theta, cos_theta) = (radians.sin(), radians.cos());
+
+        // into object space: undo the translation, then the rotation
+        let local_r = Ray::new(
+            Self::rotate(r.origin() - offset, -sin_theta, cos_theta),
+            Self::rotate(r.direction(), -sin_theta, cos_theta),
+            r.time(),
+        );
+        if !self.ptr.hit(&local_r, t_min, t_max, rec) {
+            return false;
+        }
+
+        rec.p = Self::rotate(rec.p, sin_theta, cos_theta) + offset;
+        rec.normal = Self::rotate(rec.normal, sin_theta, cos_theta);
+        rec.tangent = Self::rotate(rec.tangent, sin_theta, cos_theta);
+        true
+    }
+}
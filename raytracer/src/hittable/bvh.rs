@@ -1,4 +1,6 @@
+use std::cell::Cell;
 use std::cmp::Ordering;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use std::sync::Arc;
 
 use crate::hittable::aabb::AABB;
@@ -10,6 +12,36 @@ use crate::rtweekend::random_int;
 use crate::Point3;
 use crate::{hittable::hittable_list::HittableList, hittable::Hittable};
 
+// how many heat maps want nodes counted; other renders pay nothing
+static NODE_COUNTERS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // boxes tested by this thread, for the traversal heat map
+    static NODES_VISITED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts visited nodes for `take_nodes_visited` until the matching
+/// `stop_counting_nodes`.
+pub fn start_counting_nodes() {
+    NODE_COUNTERS.fetch_add(1, AtomicOrdering::Relaxed);
+}
+
+pub fn stop_counting_nodes() {
+    NODE_COUNTERS.fetch_sub(1, AtomicOrdering::Relaxed);
+}
+
+/// The number of BVH nodes this thread visited since the last call.
+pub fn take_nodes_visited() -> usize {
+    NODES_VISITED.with(|n| n.replace(0))
+}
+
+/// Counts one node toward the heat map, for other acceleration structures.
+pub(crate) fn count_node_visit() {
+    if NODE_COUNTERS.load(AtomicOrdering::Relaxed) > 0 {
+        NODES_VISITED.with(|n| n.set(n.get() + 1));
+    }
+}
+
 #[allow(clippy::upper_case_acronyms)]
 pub struct BVH {
     left: Arc<dyn Hittable + Send + Sync>,
@@ -132,6 +164,7 @@ impl Hittable for BVH {
     }
 
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        count_node_visit();
         if !self.tree_box.hit(r, t_min, t_max) {
             return false;
         }
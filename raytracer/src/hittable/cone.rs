@@ -0,0 +1,188 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::{
+    aabb::AABB, area_pdf_value, azimuth, random_to_surface, uniform_surface_pdf, HitRecord,
+    Hittable, Material, SurfaceSample,
+};
+use crate::{pdf::onb::Onb, ray::Ray, rtweekend::random_double_unit, Point3, Vec3};
+
+/// A cone closed at its base, with its tip `height` along `axis`. On the side
+/// `u` goes around and `v` up; on the base `v` goes out from the center.
+pub struct Cone {
+    base: Point3,
+    radius: f64,
+    height: f64,
+    uvw: Onb, // w is the axis
+    mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl Cone {
+    pub fn new(
+        base: Point3,
+        axis: Vec3,
+        radius: f64,
+        height: f64,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self {
+            base,
+            radius,
+            height,
+            uvw: Onb::build_from_w(axis),
+            mat_ptr,
+        }
+    }
+
+    fn side_area(&self) -> f64 {
+        PI * self.radius * (self.radius * self.radius + self.height * self.height).sqrt()
+    }
+
+    fn base_area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+
+    fn area(&self) -> f64 {
+        self.side_area() + self.base_area()
+    }
+
+    /// The outward normal of the side at local point `p`.
+    fn side_normal(&self, p: &Vec3) -> Vec3 {
+        let k = self.radius / self.height;
+        Vec3::new(p.x(), p.y(), k * k * (self.height - p.z())).unit()
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let o = self.uvw.to_local(&(r.origin() - self.base));
+        let d = self.uvw.to_local(&r.direction());
+        // (t, outward normal, u, v) in local space
+        let mut closest: Option<(f64, Vec3, f64, f64)> = None;
+        let mut t_far = t_max;
+
+        // x² + y² = k²(h - z)²
+        let k2 = (self.radius / self.height).powi(2);
+        let h = self.height - o.z();
+        let a = d.x() * d.x() + d.y() * d.y() - k2 * d.z() * d.z();
+        let half_b = o.x() * d.x() + o.y() * d.y() + k2 * h * d.z();
+        let c = o.x() * o.x() + o.y() * o.y() - k2 * h * h;
+        let mut roots = Vec::new();
+        if a.abs() < 1e-12 {
+            if half_b.abs() > 1e-12 {
+                roots.push(-c / (2. * half_b));
+            }
+        } else {
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0. {
+                let sqrtd = discriminant.sqrt();
+                let (t0, t1) = ((-half_b - sqrtd) / a, (-half_b + sqrtd) / a);
+                roots.push(t0.min(t1));
+                roots.push(t0.max(t1));
+            }
+        }
+        for root in roots {
+            let p = o + root * d;
+            // the other nappe of the double cone lies above the tip
+            if root > t_min && root < t_far && p.z() >= 0. && p.z() <= self.height {
+                closest = Some((root, self.side_normal(&p), azimuth(&p), p.z() / self.height));
+                t_far = root;
+            }
+        }
+
+        if d.z().abs() > 1e-12 {
+            let t = -o.z() / d.z();
+            let p = o + t * d;
+            let distance = (p.x() * p.x() + p.y() * p.y()).sqrt();
+            if t > t_min && t < t_far && distance <= self.radius {
+                let normal = Vec3::new(0., 0., -1.);
+                closest = Some((t, normal, azimuth(&p), distance / self.radius));
+            }
+        }
+
+        match closest {
+            Some((t, normal, u, v)) => {
+                rec.t = t;
+                rec.p = r.at(t);
+                rec.u = u;
+                rec.v = v;
+                rec.set_face_normal(r, self.uvw.local_vec(&normal));
+                rec.mat_ptr = Some(self.mat_ptr.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut AABB) -> bool {
+        let tip = self.base + self.height * self.uvw.w();
+        *output_box = AABB::surrounding_box(
+            &AABB::around_circle(self.base, self.uvw.w(), self.radius),
+            &AABB::from_points(&[tip]),
+        );
+        true
+    }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        area_pdf_value(self, self.area(), o, v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        random_to_surface(self, o)
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let (local_p, local_normal) = if random_double_unit() * self.area() < self.side_area() {
+            // the side's area grows with the square of the distance to the tip
+            let from_tip = random_double_unit().sqrt();
+            let phi = 2. * PI * random_double_unit();
+            let p = Vec3::new(
+                self.radius * from_tip * phi.cos(),
+                self.radius * from_tip * phi.sin(),
+                self.height * (1. - from_tip),
+            );
+            (p, self.side_normal(&p))
+        } else {
+            let d = Vec3::random_in_unit_disk() * self.radius;
+            (d, Vec3::new(0., 0., -1.))
+        };
+        Some(SurfaceSample {
+            p: self.base + self.uvw.local_vec(&local_p),
+            normal: self.uvw.local_vec(&local_normal),
+            pdf: 1. / self.area(),
+        })
+    }
+
+    fn surface_pdf(&self, o: &Point3, v: &Vec3) -> f64 {
+        uniform_surface_pdf(self, self.area(), o, v)
+    }
+}
+
+#[test]
+fn test_cone_hit() {
+    use crate::material::Lambertian;
+    use crate::Color3;
+
+    let cone = Cone::new(
+        Point3::zero(),
+        Vec3::new(0., 1., 0.),
+        1.,
+        2.,
+        Arc::new(Lambertian::new(Color3::ones())),
+    );
+    let mut rec = HitRecord::new();
+
+    // halfway up, the side is half as far out
+    let r = Ray::new(Point3::new(-5., 1., 0.), Vec3::new(1., 0., 0.), 0.);
+    assert!(cone.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.p - Point3::new(-0.5, 1., 0.)).length() < 1e-9);
+
+    // nothing above the tip
+    let r = Ray::new(Point3::new(-5., 3., 0.), Vec3::new(1., 0., 0.), 0.);
+    assert!(!cone.hit(&r, 0.001, f64::INFINITY, &mut rec));
+
+    // the base faces down
+    let r = Ray::new(Point3::new(0.2, -5., 0.), Vec3::new(0., 1., 0.), 0.);
+    assert!(cone.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert_eq!(rec.t, 5.);
+    assert_eq!(rec.normal, Vec3::new(0., -1., 0.));
+}
@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use super::{aabb::AABB, HitRecord, Hittable};
+use crate::{ray::Ray, Point3};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    Difference, // `a` with `b` cut away
+}
+
+impl CsgOp {
+    fn inside(&self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            CsgOp::Union => in_a || in_b,
+            CsgOp::Intersection => in_a && in_b,
+            CsgOp::Difference => in_a && !in_b,
+        }
+    }
+}
+
+/// A boolean combination of two closed hittables. Each surface point keeps
+/// the material and UVs of the child it came from.
+pub struct Csg {
+    op: CsgOp,
+    a: Arc<dyn Hittable + Send + Sync>,
+    b: Arc<dyn Hittable + Send + Sync>,
+}
+
+impl Csg {
+    pub fn new(
+        op: CsgOp,
+        a: Arc<dyn Hittable + Send + Sync>,
+        b: Arc<dyn Hittable + Send + Sync>,
+    ) -> Self {
+        Self { op, a, b }
+    }
+}
+
+/// Every crossing of `object` along the whole line of `r`, nearest first.
+/// Entering crossings are the ones with `front_face` set.
+fn crossings(object: &dyn Hittable, r: &Ray) -> Vec<HitRecord> {
+    let eps = 0.0001 / r.direction().length();
+    let mut found = Vec::new();
+    let mut t_min = -f64::INFINITY;
+    let mut rec = HitRecord::new();
+    while object.hit(r, t_min, f64::INFINITY, &mut rec) {
+        t_min = rec.t + eps;
+        found.push(rec.clone());
+    }
+    found
+}
+
+impl Hittable for Csg {
+    /// Walks the crossings of both children in order, keeping track of which
+    /// of them the ray is inside, and stops at the first place where that
+    /// changes whether it is inside the combination.
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let mut events: Vec<(HitRecord, bool)> = crossings(self.a.as_ref(), r)
+            .into_iter()
+            .map(|h| (h, true))
+            .chain(
+                crossings(self.b.as_ref(), r)
+                    .into_iter()
+                    .map(|h| (h, false)),
+            )
+            .collect();
+        events.sort_by(|x, y| x.0.t.partial_cmp(&y.0.t).unwrap());
+
+        let (mut in_a, mut in_b) = (false, false);
+        for (event, from_a) in events {
+            if event.t >= t_max {
+                break;
+            }
+            let was_inside = self.op.inside(in_a, in_b);
+            if from_a {
+                in_a = event.front_face;
+            } else {
+                in_b = event.front_face;
+            }
+            let inside = self.op.inside(in_a, in_b);
+            if inside != was_inside && event.t > t_min {
+                // the normal already faces the ray; only which side the
+                // ray is on can differ from the child, e.g. inside a cut
+                *rec = event;
+                rec.front_face = inside;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut AABB) -> bool {
+        let mut box_a = AABB::new(Point3::zero(), Point3::zero());
+        let mut box_b = AABB::new(Point3::zero(), Point3::zero());
+        if !self.a.bounding_box(time0, time1, &mut box_a) {
+            return false;
+        }
+        *output_box = match self.op {
+            CsgOp::Difference => box_a,
+            _ => {
+                if !self.b.bounding_box(time0, time1, &mut box_b) {
+                    return false;
+                }
+                if self.op == CsgOp::Union {
+                    AABB::surrounding_box(&box_a, &box_b)
+                } else {
+                    let small = Point3::new(
+                        box_a.min().x().max(box_b.min().x()),
+                        box_a.min().y().max(box_b.min().y()),
+                        box_a.min().z().max(box_b.min().z()),
+                    );
+                    let big = Point3::new(
+                        box_a.max().x().min(box_b.max().x()),
+                        box_a.max().y().min(box_b.max().y()),
+                        box_a.max().z().min(box_b.max().z()),
+                    );
+                    AABB::new(small, big)
+                }
+            }
+        };
+        true
+    }
+}
+
+#[test]
+fn test_csg_difference() {
+    use crate::hittable::sphere::Sphere;
+    use crate::material::Lambertian;
+    use crate::{Color3, Vec3};
+
+    let mat = Arc::new(Lambertian::new(Color3::ones()));
+    let bitten = Csg::new(
+        CsgOp::Difference,
+        Arc::new(Sphere::new(Point3::zero(), 1., mat.clone())),
+        Arc::new(Sphere::new(Point3::new(1., 0., 0.), 0.5, mat)),
+    );
+    let mut rec = HitRecord::new();
+
+    let r = Ray::new(Point3::new(-5., 0., 0.), Vec3::new(1., 0., 0.), 0.);
+    assert!(bitten.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 4.).abs() < 1e-9);
+
+    // into the bite, onto the inside of the cut sphere
+    let r = Ray::new(Point3::new(5., 0., 0.), Vec3::new(-1., 0., 0.), 0.);
+    assert!(bitten.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 4.5).abs() < 1e-9);
+    assert!(rec.front_face);
+    assert_eq!(rec.normal, Vec3::new(1., 0., 0.));
+
+    // the far side of the bite from within the solid
+    let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(1., 0., 0.), 0.);
+    assert!(bitten.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 0.5).abs() < 1e-9);
+    assert!(!rec.front_face);
+}
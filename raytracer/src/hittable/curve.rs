@@ -0,0 +1,202 @@
+use std::{f64::consts::SQRT_2, sync::Arc};
+
+use super::{aabb::AABB, HitRecord, Hittable, Material};
+use crate::{pdf::onb::Onb, ray::Ray, Point3, Vec3};
+
+/// A strand along a cubic Bézier segment whose width changes linearly from
+/// one end to the other, for hair, fur and grass. It is intersected as a
+/// ribbon facing the ray and shaded as if it were round: `u` runs along the
+/// curve, `v` across it, and the tangent is recorded for hair materials.
+pub struct Curve {
+    cp: [Point3; 4],
+    width: (f64, f64), // at u = 0 and u = 1
+    mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+fn bezier(cp: &[Point3; 4], u: f64) -> Point3 {
+    let s = 1. - u;
+    s * s * s * cp[0] + 3. * s * s * u * cp[1] + 3. * s * u * u * cp[2] + u * u * u * cp[3]
+}
+
+fn bezier_derivative(cp: &[Point3; 4], u: f64) -> Vec3 {
+    let s = 1. - u;
+    3. * (s * s * (cp[1] - cp[0]) + 2. * s * u * (cp[2] - cp[1]) + u * u * (cp[3] - cp[2]))
+}
+
+/// Both halves of the segment, by de Casteljau's construction.
+fn split(cp: &[Point3; 4]) -> ([Point3; 4], [Point3; 4]) {
+    let a = (cp[0] + cp[1]) / 2.;
+    let b = (cp[1] + cp[2]) / 2.;
+    let c = (cp[2] + cp[3]) / 2.;
+    let ab = (a + b) / 2.;
+    let bc = (b + c) / 2.;
+    let middle = (ab + bc) / 2.;
+    ([cp[0], a, ab, middle], [middle, bc, c, cp[3]])
+}
+
+impl Curve {
+    pub fn new(
+        cp: [Point3; 4],
+        width0: f64,
+        width1: f64,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self {
+            cp,
+            width: (width0, width1),
+            mat_ptr,
+        }
+    }
+
+    fn width_at(&self, u: f64) -> f64 {
+        (1. - u) * self.width.0 + u * self.width.1
+    }
+
+    /// The nearest crossing of the ray's axis by the part of the curve over
+    /// `u0..u1`, given in the ray's frame where the ray runs along +z from the
+    /// origin. Halves the segment until it is flat enough to be a line.
+    #[allow(clippy::too_many_arguments)]
+    fn intersect(
+        &self,
+        cp: &[Point3; 4],
+        u0: f64,
+        u1: f64,
+        depth: usize,
+        z_min: f64,
+        z_max: f64,
+    ) -> Option<(f64, f64)> {
+        let half_width = self.width_at(u0).max(self.width_at(u1)) / 2.;
+        let mut low = cp[0];
+        let mut high = cp[0];
+        for p in &cp[1..] {
+            for a in 0..3 {
+                low[a] = low[a].min(p[a]);
+                high[a] = high[a].max(p[a]);
+            }
+        }
+        if low.x() - half_width > 0.
+            || high.x() + half_width < 0.
+            || low.y() - half_width > 0.
+            || high.y() + half_width < 0.
+            || low.z() - half_width > z_max
+            || high.z() + half_width < z_min
+        {
+            return None;
+        }
+
+        if depth > 0 {
+            let (left, right) = split(cp);
+            let middle = (u0 + u1) / 2.;
+            let near = self.intersect(&left, u0, middle, depth - 1, z_min, z_max);
+            let z_max = near.map_or(z_max, |(z, _)| z);
+            let far = self.intersect(&right, middle, u1, depth - 1, z_min, z_max);
+            return far.or(near);
+        }
+
+        // flat enough: find where the line between the ends passes the axis
+        let segment = Vec3::new(cp[3].x() - cp[0].x(), cp[3].y() - cp[0].y(), 0.);
+        let denominator = segment.squared_length();
+        if denominator == 0. {
+            return None;
+        }
+        let w = -(cp[0].x() * segment.x() + cp[0].y() * segment.y()) / denominator;
+        // past the ends of inner pieces, so the outside of bends has no cracks
+        if (w < 0. && u0 == 0.) || (w > 1. && u1 == 1.) {
+            return None;
+        }
+        let w = w.clamp(0., 1.);
+        let u = u0 + (u1 - u0) * w;
+        let p = bezier(cp, w);
+        let half_width = self.width_at(u) / 2.;
+        if p.x() * p.x() + p.y() * p.y() > half_width * half_width {
+            return None;
+        }
+        if p.z() < z_min || p.z() > z_max {
+            return None;
+        }
+        Some((p.z(), u))
+    }
+}
+
+impl Hittable for Curve {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let speed = r.direction().length();
+        let frame = Onb::build_from_w(r.direction());
+        let local = self.cp.map(|p| frame.to_local(&(p - r.origin())));
+
+        // split about as often as it takes for the pieces to look straight
+        let mut bend: f64 = 0.;
+        for i in 0..2 {
+            let second_difference = local[i] - 2. * local[i + 1] + local[i + 2];
+            for a in 0..3 {
+                bend = bend.max(second_difference[a].abs());
+            }
+        }
+        let eps = self.width.0.max(self.width.1) / 20.;
+        let depth = ((SQRT_2 * 6. * bend / (8. * eps)).log2() / 2.).round();
+        let depth = depth.clamp(0., 10.) as usize;
+
+        let (z, u) = match self.intersect(&local, 0., 1., depth, t_min * speed, t_max * speed) {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        let t = z / speed;
+        let p = r.at(t);
+        let tangent = bezier_derivative(&self.cp, u).unit();
+        // the flat normal faces the ray; across it, a round strand turns away
+        let to_eye = -r.direction() / speed;
+        let flat = (to_eye - (to_eye * tangent) * tangent).unit();
+        let side = Vec3::cross(flat, tangent);
+        let h = ((p - bezier(&self.cp, u)) * side / (self.width_at(u) / 2.)).clamp(-1., 1.);
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = u;
+        rec.v = (h + 1.) / 2.;
+        rec.tangent = tangent;
+        rec.set_face_normal(r, (1. - h * h).sqrt() * flat + h * side);
+        rec.mat_ptr = Some(self.mat_ptr.clone());
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut AABB) -> bool {
+        let control = AABB::from_points(&self.cp);
+        let half_width = Vec3::ones() * self.width.0.max(self.width.1) / 2.;
+        *output_box = AABB::new(control.min() - half_width, control.max() + half_width);
+        true
+    }
+}
+
+#[test]
+fn test_curve_hit() {
+    use crate::material::Lambertian;
+    use crate::Color3;
+
+    // an arch rising from x = -1 to x = 1, thinning towards its end
+    let curve = Curve::new(
+        [
+            Point3::new(-1., 0., 0.),
+            Point3::new(-1., 1., 0.),
+            Point3::new(1., 1., 0.),
+            Point3::new(1., 0., 0.),
+        ],
+        0.2,
+        0.1,
+        Arc::new(Lambertian::new(Color3::ones())),
+    );
+    let mut rec = HitRecord::new();
+
+    // straight onto the top of the arch, 0.75 high
+    let r = Ray::new(Point3::new(0., 0.75, 5.), Vec3::new(0., 0., -1.), 0.);
+    assert!(curve.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 5.).abs() < 1e-6);
+    assert!((rec.u - 0.5).abs() < 1e-3);
+    assert!((rec.v - 0.5).abs() < 1e-3);
+    assert!((rec.tangent - Vec3::new(1., 0., 0.)).length() < 1e-6);
+    assert!((rec.normal - Vec3::new(0., 0., 1.)).length() < 1e-3);
+
+    // just past the edge of the strand
+    let r = Ray::new(Point3::new(0., 0.86, 5.), Vec3::new(0., 0., -1.), 0.);
+    assert!(!curve.hit(&r, 0.001, f64::INFINITY, &mut rec));
+}
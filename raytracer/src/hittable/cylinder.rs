@@ -0,0 +1,141 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::{
+    aabb::AABB, area_pdf_value, azimuth, random_to_surface, uniform_surface_pdf, HitRecord,
+    Hittable, Material, SurfaceSample,
+};
+use crate::{pdf::onb::Onb, ray::Ray, rtweekend::random_double_unit, Point3, Vec3};
+
+/// A cylinder closed by two caps, standing on `base` along `axis`. On the
+/// side `u` goes around and `v` up; on the caps `v` goes out from the center.
+pub struct Cylinder {
+    base: Point3,
+    radius: f64,
+    height: f64,
+    uvw: Onb, // w is the axis
+    mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl Cylinder {
+    pub fn new(
+        base: Point3,
+        axis: Vec3,
+        radius: f64,
+        height: f64,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self {
+            base,
+            radius,
+            height,
+            uvw: Onb::build_from_w(axis),
+            mat_ptr,
+        }
+    }
+
+    fn side_area(&self) -> f64 {
+        2. * PI * self.radius * self.height
+    }
+
+    fn cap_area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+
+    fn area(&self) -> f64 {
+        self.side_area() + 2. * self.cap_area()
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let o = self.uvw.to_local(&(r.origin() - self.base));
+        let d = self.uvw.to_local(&r.direction());
+        // (t, outward normal, u, v) in local space
+        let mut closest: Option<(f64, Vec3, f64, f64)> = None;
+        let mut t_far = t_max;
+
+        let a = d.x() * d.x() + d.y() * d.y();
+        let half_b = o.x() * d.x() + o.y() * d.y();
+        let c = o.x() * o.x() + o.y() * o.y() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if a > 1e-12 && discriminant >= 0. {
+            let sqrtd = discriminant.sqrt();
+            for root in [(-half_b - sqrtd) / a, (-half_b + sqrtd) / a] {
+                let p = o + root * d;
+                if root > t_min && root < t_far && p.z() >= 0. && p.z() <= self.height {
+                    let normal = Vec3::new(p.x(), p.y(), 0.) / self.radius;
+                    closest = Some((root, normal, azimuth(&p), p.z() / self.height));
+                    t_far = root;
+                }
+            }
+        }
+
+        if d.z().abs() > 1e-12 {
+            for (z, normal_z) in [(0., -1.), (self.height, 1.)] {
+                let t = (z - o.z()) / d.z();
+                let p = o + t * d;
+                let distance = (p.x() * p.x() + p.y() * p.y()).sqrt();
+                if t > t_min && t < t_far && distance <= self.radius {
+                    let normal = Vec3::new(0., 0., normal_z);
+                    closest = Some((t, normal, azimuth(&p), distance / self.radius));
+                    t_far = t;
+                }
+            }
+        }
+
+        match closest {
+            Some((t, normal, u, v)) => {
+                rec.t = t;
+                rec.p = r.at(t);
+                rec.u = u;
+                rec.v = v;
+                rec.set_face_normal(r, self.uvw.local_vec(&normal));
+                rec.mat_ptr = Some(self.mat_ptr.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut AABB) -> bool {
+        let top = self.base + self.height * self.uvw.w();
+        *output_box = AABB::surrounding_box(
+            &AABB::around_circle(self.base, self.uvw.w(), self.radius),
+            &AABB::around_circle(top, self.uvw.w(), self.radius),
+        );
+        true
+    }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        area_pdf_value(self, self.area(), o, v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        random_to_surface(self, o)
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let phi = 2. * PI * random_double_unit();
+        let (local_p, local_normal) = if random_double_unit() * self.area() < self.side_area() {
+            let z = self.height * random_double_unit();
+            let normal = Vec3::new(phi.cos(), phi.sin(), 0.);
+            (normal * self.radius + Vec3::new(0., 0., z), normal)
+        } else {
+            let d = Vec3::random_in_unit_disk() * self.radius;
+            if random_double_unit() < 0.5 {
+                (d, Vec3::new(0., 0., -1.))
+            } else {
+                (d + Vec3::new(0., 0., self.height), Vec3::new(0., 0., 1.))
+            }
+        };
+        Some(SurfaceSample {
+            p: self.base + self.uvw.local_vec(&local_p),
+            normal: self.uvw.local_vec(&local_normal),
+            pdf: 1. / self.area(),
+        })
+    }
+
+    fn surface_pdf(&self, o: &Point3, v: &Vec3) -> f64 {
+        uniform_surface_pdf(self, self.area(), o, v)
+    }
+}
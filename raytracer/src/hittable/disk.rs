@@ -0,0 +1,90 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::{
+    aabb::AABB, area_pdf_value, azimuth, random_to_surface, uniform_surface_pdf, HitRecord,
+    Hittable, Material, SurfaceSample,
+};
+use crate::{pdf::onb::Onb, ray::Ray, Point3, Vec3};
+
+/// A disk facing `normal`. `u` goes around it and `v` out from the center.
+pub struct Disk {
+    center: Point3,
+    radius: f64,
+    uvw: Onb, // w is the normal
+    mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl Disk {
+    pub fn new(
+        center: Point3,
+        normal: Vec3,
+        radius: f64,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self {
+            center,
+            radius,
+            uvw: Onb::build_from_w(normal),
+            mat_ptr,
+        }
+    }
+
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let normal = self.uvw.w();
+        let denom = r.direction() * normal;
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+        let t = (self.center - r.origin()) * normal / denom;
+        if t < t_min || t > t_max {
+            return false;
+        }
+
+        let p = r.at(t);
+        let local = self.uvw.to_local(&(p - self.center));
+        let distance = (local.x() * local.x() + local.y() * local.y()).sqrt();
+        if distance > self.radius {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = azimuth(&local);
+        rec.v = distance / self.radius;
+        rec.set_face_normal(r, normal);
+        rec.mat_ptr = Some(self.mat_ptr.clone());
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut AABB) -> bool {
+        *output_box = AABB::around_circle(self.center, self.uvw.w(), self.radius);
+        true
+    }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        area_pdf_value(self, self.area(), o, v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        random_to_surface(self, o)
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let d = Vec3::random_in_unit_disk() * self.radius;
+        Some(SurfaceSample {
+            p: self.center + self.uvw.local(d.x(), d.y(), 0.),
+            normal: self.uvw.w(),
+            pdf: 1. / self.area(),
+        })
+    }
+
+    fn surface_pdf(&self, o: &Point3, v: &Vec3) -> f64 {
+        uniform_surface_pdf(self, self.area(), o, v)
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use crate::{
     hittable::aabb::AABB,
-    hittable::{HitRecord, Hittable},
+    hittable::{HitRecord, Hittable, SurfaceSample},
     rtweekend::random_int,
     Point3,
 };
@@ -76,4 +76,23 @@ impl Hittable for HittableList {
         let int_size: isize = self.objects.len().try_into().unwrap();
         self.objects[random_int(0, int_size - 1) as usize].random(o)
     }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        if self.objects.is_empty() {
+            return None;
+        }
+        let int_size: isize = self.objects.len().try_into().unwrap();
+        let mut sample = self.objects[random_int(0, int_size - 1) as usize].sample_surface()?;
+        sample.pdf /= self.objects.len() as f64;
+        Some(sample)
+    }
+
+    fn surface_pdf(&self, o: &Point3, v: &crate::Vec3) -> f64 {
+        let weight = 1. / self.objects.len() as f64;
+        let mut sum = 0.;
+        for object in &self.objects {
+            sum += weight * object.surface_pdf(o, v);
+        }
+        sum
+    }
 }
@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use super::{aabb::AABB, HitRecord, Hittable, Material};
+use crate::{ray::Ray, rtweekend::degrees_to_radius, Point3, Vec3};
+
+type Matrix = [[f64; 3]; 3];
+
+const IDENTITY: Matrix = [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+
+fn mul(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut m = [[0.; 3]; 3];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    m
+}
+
+fn apply(m: &Matrix, v: Vec3) -> Vec3 {
+    Vec3::new(
+        m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+        m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+        m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+    )
+}
+
+fn apply_transposed(m: &Matrix, v: Vec3) -> Vec3 {
+    Vec3::new(
+        m[0][0] * v.x() + m[1][0] * v.y() + m[2][0] * v.z(),
+        m[0][1] * v.x() + m[1][1] * v.y() + m[2][1] * v.z(),
+        m[0][2] * v.x() + m[1][2] * v.y() + m[2][2] * v.z(),
+    )
+}
+
+/// An affine map from object space to world space, kept with its inverse.
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    m: Matrix,
+    inv: Matrix,
+    offset: Vec3,
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self {
+            m: IDENTITY,
+            inv: IDENTITY,
+            offset: Vec3::zero(),
+        }
+    }
+
+    pub fn translate(offset: Vec3) -> Self {
+        Self {
+            offset,
+            ..Self::identity()
+        }
+    }
+
+    /// Scales each axis by the matching component of `s`, none of them zero.
+    pub fn scale(s: Vec3) -> Self {
+        let mut t = Self::identity();
+        for a in 0..3 {
+            t.m[a][a] = s[a];
+            t.inv[a][a] = 1. / s[a];
+        }
+        t
+    }
+
+    /// Rotates by `angle` degrees counterclockwise around `axis`.
+    pub fn rotate(axis: Vec3, angle: f64) -> Self {
+        let a = axis.unit();
+        let radians = degrees_to_radius(angle);
+        let (sin, cos) = (radians.sin(), radians.cos());
+        let mut m = [[0.; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = a[i] * a[j] * (1. - cos) + if i == j { cos } else { 0. };
+            }
+        }
+        m[0][1] -= a.z() * sin;
+        m[0][2] += a.y() * sin;
+        m[1][0] += a.z() * sin;
+        m[1][2] -= a.x() * sin;
+        m[2][0] -= a.y() * sin;
+        m[2][1] += a.x() * sin;
+        let mut inv = m;
+        for (i, row) in inv.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = m[j][i];
+            }
+        }
+        Self {
+            m,
+            inv,
+            offset: Vec3::zero(),
+        }
+    }
+
+    /// This transform followed by `next`.
+    pub fn then(&self, next: Transform) -> Self {
+        Self {
+            m: mul(&next.m, &self.m),
+            inv: mul(&self.inv, &next.inv),
+            offset: apply(&next.m, self.offset) + next.offset,
+        }
+    }
+
+    pub fn point(&self, p: Point3) -> Point3 {
+        apply(&self.m, p) + self.offset
+    }
+
+    /// Normals go through the inverse transpose to stay perpendicular.
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        apply_transposed(&self.inv, n).unit()
+    }
+
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        apply(&self.m, v)
+    }
+
+    pub fn inverse_point(&self, p: Point3) -> Point3 {
+        apply(&self.inv, p - self.offset)
+    }
+
+    pub fn inverse_vector(&self, v: Vec3) -> Vec3 {
+        apply(&self.inv, v)
+    }
+
+    /// The world box around `local`, a box in object space.
+    pub fn bounding_box(&self, local: &AABB) -> AABB {
+        let mut corners = Vec::with_capacity(8);
+        for i in 0..8 {
+            let pick = |bit: usize, a: usize| {
+                if i & bit == 0 {
+                    local.min()[a]
+                } else {
+                    local.max()[a]
+                }
+            };
+            corners.push(self.point(Point3::new(pick(1, 0), pick(2, 1), pick(4, 2))));
+        }
+        AABB::from_points(&corners)
+    }
+}
+
+/// One placement of a shared object, e.g. a prebuilt `BVH`, so that many
+/// copies cost the memory of one. The material of every hit can be replaced
+/// so that copies need not look alike.
+pub struct Instance {
+    ptr: Arc<dyn Hittable + Send + Sync>,
+    transform: Transform,
+    mat_ptr: Option<Arc<dyn Material + Send + Sync>>,
+}
+
+impl Instance {
+    pub fn new(ptr: Arc<dyn Hittable + Send + Sync>, transform: Transform) -> Self {
+        Self {
+            ptr,
+            transform,
+            mat_ptr: None,
+        }
+    }
+
+    pub fn with_material(mut self, mat_ptr: Arc<dyn Material + Send + Sync>) -> Self {
+        self.mat_ptr = Some(mat_ptr);
+        self
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        // the direction is not renormalized, so `t` means the same in both spaces
+        let local_r = Ray::new(
+            self.transform.inverse_point(r.origin()),
+            self.transform.inverse_vector(r.direction()),
+            r.time(),
+        );
+        if !self.ptr.hit(&local_r, t_min, t_max, rec) {
+            return false;
+        }
+
+        let outward_normal = if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
+        };
+        rec.p = self.transform.point(rec.p);
+        rec.tangent = self.transform.vector(rec.tangent);
+        rec.set_face_normal(r, self.transform.normal(outward_normal));
+        if let Some(mat_ptr) = &self.mat_ptr {
+            rec.mat_ptr = Some(mat_ptr.clone());
+        }
+        true
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut AABB) -> bool {
+        let mut local = AABB::new(Point3::zero(), Point3::zero());
+        if !self.ptr.bounding_box(time0, time1, &mut local) {
+            return false;
+        }
+        *output_box = self.transform.bounding_box(&local);
+        true
+    }
+}
+
+#[test]
+fn test_instance_hit() {
+    use crate::hittable::sphere::Sphere;
+    use crate::material::{Lambertian, Metal};
+    use crate::Color3;
+
+    let unit = Arc::new(Sphere::new(
+        Point3::zero(),
+        1.,
+        Arc::new(Lambertian::new(Color3::ones())),
+    ));
+    let metal: Arc<dyn Material + Send + Sync> = Arc::new(Metal::new(Color3::ones(), 0.));
+    let squashed = Instance::new(
+        unit,
+        Transform::scale(Vec3::new(2., 1., 1.))
+            .then(Transform::rotate(Vec3::new(0., 0., 1.), 90.))
+            .then(Transform::translate(Vec3::new(0., 0., 5.))),
+    )
+    .with_material(metal.clone());
+    let mut rec = HitRecord::new();
+
+    // stretched along x, then turned to stand along y
+    let r = Ray::new(Point3::new(0., 5., 5.), Vec3::new(0., -1., 0.), 0.);
+    assert!(squashed.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 3.).abs() < 1e-9);
+    assert!((rec.p - Point3::new(0., 2., 5.)).length() < 1e-9);
+    assert!((rec.normal - Vec3::new(0., 1., 0.)).length() < 1e-9);
+    assert!(Arc::ptr_eq(rec.mat_ptr.as_ref().unwrap(), &metal));
+
+    let r = Ray::new(Point3::new(5., 0., 5.), Vec3::new(-1., 0., 0.), 0.);
+    assert!(squashed.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 4.).abs() < 1e-9);
+}
@@ -0,0 +1,347 @@
+use std::{collections::HashMap, sync::Arc};
+
+use super::{hittable_list::HittableList, triangle::Triangle, Material};
+use crate::{material::texture::Texture, Point3, Vec3};
+
+/// A polygon mesh, kept as shared vertices so that it can be refined before
+/// it is cut into triangles for the `BVH`. Faces list vertex indices
+/// counterclockwise as seen from outside.
+#[derive(Clone)]
+pub struct Mesh {
+    pub positions: Vec<Point3>,
+    pub uvs: Vec<(f64, f64)>,
+    pub faces: Vec<Vec<usize>>,
+}
+
+// the faces on each side of every edge
+type EdgeFaces = HashMap<(usize, usize), Vec<usize>>;
+
+/// Both vertices of an edge, smaller index first.
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+fn midpoint_uv(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    ((a.0 + b.0) / 2., (a.1 + b.1) / 2.)
+}
+
+impl Mesh {
+    pub fn new(positions: Vec<Point3>, faces: Vec<Vec<usize>>) -> Self {
+        let uvs = vec![(0., 0.); positions.len()];
+        Self {
+            positions,
+            uvs,
+            faces,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_uvs(mut self, uvs: Vec<(f64, f64)>) -> Self {
+        self.uvs = uvs;
+        self
+    }
+
+    /// The faces on each side of every edge, and for every vertex the other
+    /// ends of its edges.
+    fn adjacency(&self) -> (EdgeFaces, Vec<Vec<usize>>) {
+        let mut edges = EdgeFaces::new();
+        let mut neighbors = vec![Vec::new(); self.positions.len()];
+        for (f, face) in self.faces.iter().enumerate() {
+            for i in 0..face.len() {
+                let (a, b) = (face[i], face[(i + 1) % face.len()]);
+                let faces = edges.entry(edge_key(a, b)).or_default();
+                if faces.is_empty() {
+                    neighbors[a].push(b);
+                    neighbors[b].push(a);
+                }
+                faces.push(f);
+            }
+        }
+        (edges, neighbors)
+    }
+
+    /// The ends of the open edges at `v`, where the surface has a border.
+    fn boundary_neighbors(edges: &EdgeFaces, neighbors: &[usize], v: usize) -> Vec<usize> {
+        neighbors
+            .iter()
+            .copied()
+            .filter(|&n| edges[&edge_key(v, n)].len() == 1)
+            .collect()
+    }
+
+    /// One level of Loop subdivision, which splits every triangle into four
+    /// and converges to a smooth surface. Every face must be a triangle.
+    pub fn subdivide_loop(&self) -> Self {
+        assert!(
+            self.faces.iter().all(|face| face.len() == 3),
+            "Loop subdivision needs a triangle mesh"
+        );
+        let (edges, neighbors) = self.adjacency();
+        let mut positions = Vec::with_capacity(self.positions.len() + edges.len());
+        let mut uvs = Vec::with_capacity(positions.capacity());
+
+        for (v, around) in neighbors.iter().enumerate() {
+            let p = self.positions[v];
+            let boundary = Self::boundary_neighbors(&edges, around, v);
+            positions.push(if boundary.len() == 2 {
+                0.75 * p + 0.125 * (self.positions[boundary[0]] + self.positions[boundary[1]])
+            } else if around.is_empty() {
+                p
+            } else {
+                let n = around.len() as f64;
+                let beta = if around.len() == 3 {
+                    3. / 16.
+                } else {
+                    3. / (8. * n)
+                };
+                let sum = around
+                    .iter()
+                    .fold(Vec3::zero(), |sum, &u| sum + self.positions[u]);
+                (1. - n * beta) * p + beta * sum
+            });
+            uvs.push(self.uvs[v]);
+        }
+
+        // in a fixed order, so the same mesh always comes out numbered the same
+        let mut keys: Vec<(usize, usize)> = edges.keys().copied().collect();
+        keys.sort_unstable();
+        let mut edge_points = HashMap::new();
+        for (a, b) in keys {
+            let faces = &edges[&(a, b)];
+            let ends = self.positions[a] + self.positions[b];
+            let p = if faces.len() == 2 {
+                let opposite = |f: usize| {
+                    let face = &self.faces[f];
+                    face.iter().copied().find(|&v| v != a && v != b).unwrap()
+                };
+                0.375 * ends
+                    + 0.125
+                        * (self.positions[opposite(faces[0])] + self.positions[opposite(faces[1])])
+            } else {
+                0.5 * ends
+            };
+            edge_points.insert((a, b), positions.len());
+            positions.push(p);
+            uvs.push(midpoint_uv(self.uvs[a], self.uvs[b]));
+        }
+
+        let mut faces = Vec::with_capacity(self.faces.len() * 4);
+        for face in &self.faces {
+            let (a, b, c) = (face[0], face[1], face[2]);
+            let ab = edge_points[&edge_key(a, b)];
+            let bc = edge_points[&edge_key(b, c)];
+            let ca = edge_points[&edge_key(c, a)];
+            faces.push(vec![a, ab, ca]);
+            faces.push(vec![ab, b, bc]);
+            faces.push(vec![ca, bc, c]);
+            faces.push(vec![ab, bc, ca]);
+        }
+        Self {
+            positions,
+            uvs,
+            faces,
+        }
+    }
+
+    /// One level of Catmull-Clark subdivision, which turns any polygons into
+    /// quads and converges to a smooth surface.
+    pub fn subdivide_catmull_clark(&self) -> Self {
+        let (edges, neighbors) = self.adjacency();
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+
+        let face_points: Vec<Point3> = self
+            .faces
+            .iter()
+            .map(|face| {
+                face.iter()
+                    .fold(Vec3::zero(), |sum, &v| sum + self.positions[v])
+                    / face.len() as f64
+            })
+            .collect();
+        let mut faces_around = vec![Vec::new(); self.positions.len()];
+        for (f, face) in self.faces.iter().enumerate() {
+            for &v in face {
+                faces_around[v].push(f);
+            }
+        }
+
+        for (v, around) in neighbors.iter().enumerate() {
+            let p = self.positions[v];
+            let boundary = Self::boundary_neighbors(&edges, around, v);
+            positions.push(if boundary.len() == 2 {
+                0.75 * p + 0.125 * (self.positions[boundary[0]] + self.positions[boundary[1]])
+            } else if around.is_empty() {
+                p
+            } else {
+                let n = around.len() as f64;
+                let face_average = faces_around[v]
+                    .iter()
+                    .fold(Vec3::zero(), |sum, &f| sum + face_points[f])
+                    / faces_around[v].len() as f64;
+                let edge_average = around
+                    .iter()
+                    .fold(Vec3::zero(), |sum, &u| sum + (p + self.positions[u]) / 2.)
+                    / n;
+                (face_average + 2. * edge_average + (n - 3.) * p) / n
+            });
+            uvs.push(self.uvs[v]);
+        }
+
+        // in a fixed order, so the same mesh always comes out numbered the same
+        let mut keys: Vec<(usize, usize)> = edges.keys().copied().collect();
+        keys.sort_unstable();
+        let mut edge_points = HashMap::new();
+        for (a, b) in keys {
+            let faces = &edges[&(a, b)];
+            let ends = self.positions[a] + self.positions[b];
+            let p = if faces.len() == 2 {
+                (ends + face_points[faces[0]] + face_points[faces[1]]) / 4.
+            } else {
+                ends / 2.
+            };
+            edge_points.insert((a, b), positions.len());
+            positions.push(p);
+            uvs.push(midpoint_uv(self.uvs[a], self.uvs[b]));
+        }
+
+        let mut faces = Vec::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            let center = positions.len();
+            positions.push(face_points[f]);
+            let (u, v) = face.iter().fold((0., 0.), |(u, v), &i| {
+                (u + self.uvs[i].0, v + self.uvs[i].1)
+            });
+            uvs.push((u / face.len() as f64, v / face.len() as f64));
+
+            let k = face.len();
+            for i in 0..k {
+                let (previous, current, next) = (face[(i + k - 1) % k], face[i], face[(i + 1) % k]);
+                faces.push(vec![
+                    current,
+                    edge_points[&edge_key(current, next)],
+                    center,
+                    edge_points[&edge_key(previous, current)],
+                ]);
+            }
+        }
+        Self {
+            positions,
+            uvs,
+            faces,
+        }
+    }
+
+    /// Area weighted normals at every vertex.
+    pub fn vertex_normals(&self) -> Vec<Vec3> {
+        let mut normals = vec![Vec3::zero(); self.positions.len()];
+        for face in &self.faces {
+            // twice the area, facing out; exact for planar polygons
+            let mut n = Vec3::zero();
+            for i in 1..face.len() - 1 {
+                n += Vec3::cross(
+                    self.positions[face[i]] - self.positions[face[0]],
+                    self.positions[face[i + 1]] - self.positions[face[0]],
+                );
+            }
+            for &v in face {
+                normals[v] += n;
+            }
+        }
+        normals
+            .into_iter()
+            .map(|n| if n.near_zero() { n } else { n.unit() })
+            .collect()
+    }
+
+    /// Moves every vertex along its normal by `scale` times the brightness
+    /// of `texture` there. Subdivide first so there are vertices enough to
+    /// carry the detail.
+    pub fn displace(&self, texture: &dyn Texture, scale: f64) -> Self {
+        let normals = self.vertex_normals();
+        let mut displaced = self.clone();
+        for (v, p) in displaced.positions.iter_mut().enumerate() {
+            let (u, w) = self.uvs[v];
+            let color = texture.value(u, w, p);
+            let height = (color.x() + color.y() + color.z()) / 3.;
+            *p += scale * height * normals[v];
+        }
+        displaced
+    }
+
+    /// Cuts every face into a fan of triangles shaded with the vertex
+    /// normals and textured with the vertex UVs, ready to go into a `BVH`.
+    pub fn triangulate(&self, mat_ptr: Arc<dyn Material + Send + Sync>) -> HittableList {
+        let normals = self.vertex_normals();
+        let mut triangles = HittableList::new();
+        for face in &self.faces {
+            for i in 1..face.len() - 1 {
+                let (a, b, c) = (face[0], face[i], face[i + 1]);
+                triangles.add(Arc::new(
+                    Triangle::new(
+                        self.positions[a],
+                        self.positions[b],
+                        self.positions[c],
+                        mat_ptr.clone(),
+                    )
+                    .with_vertex_normals(normals[a], normals[b], normals[c])
+                    .with_uvs(self.uvs[a], self.uvs[b], self.uvs[c]),
+                ));
+            }
+        }
+        triangles
+    }
+}
+
+#[test]
+fn test_subdivision_counts() {
+    let cube = Mesh::new(
+        vec![
+            Point3::new(-1., -1., -1.),
+            Point3::new(1., -1., -1.),
+            Point3::new(1., 1., -1.),
+            Point3::new(-1., 1., -1.),
+            Point3::new(-1., -1., 1.),
+            Point3::new(1., -1., 1.),
+            Point3::new(1., 1., 1.),
+            Point3::new(-1., 1., 1.),
+        ],
+        vec![
+            vec![0, 3, 2, 1],
+            vec![4, 5, 6, 7],
+            vec![0, 1, 5, 4],
+            vec![2, 3, 7, 6],
+            vec![0, 4, 7, 3],
+            vec![1, 2, 6, 5],
+        ],
+    );
+    let smooth = cube.subdivide_catmull_clark();
+    assert_eq!(smooth.positions.len(), 8 + 12 + 6);
+    assert_eq!(smooth.faces.len(), 24);
+    // corners are pulled in towards the limit surface, face centers stay
+    assert!((smooth.positions[0] - Point3::new(-5., -5., -5.) / 9.).length() < 1e-9);
+    assert_eq!(smooth.positions[25], Point3::new(1., 0., 0.));
+
+    let tetrahedron = Mesh::new(
+        vec![
+            Point3::new(1., 1., 1.),
+            Point3::new(1., -1., -1.),
+            Point3::new(-1., 1., -1.),
+            Point3::new(-1., -1., 1.),
+            // not part of any face, and left where it is
+            Point3::new(5., 5., 5.),
+        ],
+        vec![vec![0, 1, 2], vec![0, 3, 1], vec![0, 2, 3], vec![1, 3, 2]],
+    );
+    let smooth = tetrahedron.subdivide_loop();
+    assert_eq!(smooth.positions.len(), 5 + 6);
+    assert_eq!(smooth.positions[4], Point3::new(5., 5., 5.));
+    assert_eq!(smooth.faces.len(), 16);
+    assert_eq!(
+        smooth
+            .triangulate(Arc::new(crate::material::Lambertian::new(Vec3::ones())))
+            .objects
+            .len(),
+        16
+    );
+}
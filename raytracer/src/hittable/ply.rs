@@ -0,0 +1,472 @@
+use std::{
+    io::{Error, ErrorKind, Result},
+    sync::Arc,
+};
+
+use super::{
+    bvh::BVH, hittable_list::HittableList, mesh::Mesh, sphere::Sphere, triangle::Triangle, Material,
+};
+use crate::{
+    material::{texture::VertexColors, Lambertian},
+    Color3, Point3, Vec3,
+};
+
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return Err(invalid(format!("unknown PLY type {}", name))),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    /// What a color stored as this type is divided by to bring it to 0..1.
+    fn color_scale(self) -> f64 {
+        match self {
+            Self::U8 => 255.,
+            Self::U16 => 65535.,
+            _ => 1.,
+        }
+    }
+}
+
+enum Property {
+    Scalar(String, Scalar),
+    List(String, Scalar, Scalar), // the count's type, then the items'
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Self::Scalar(name, _) | Self::List(name, _, _) => name,
+        }
+    }
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Reads values one at a time from the data after the header.
+struct Body<'a> {
+    format: Format,
+    data: &'a [u8],
+    pos: usize,
+}
+
+fn array<const N: usize>(bytes: &[u8], format: Format) -> [u8; N] {
+    let mut a = [0; N];
+    a.copy_from_slice(bytes);
+    if format == Format::BigEndian {
+        a.reverse();
+    }
+    a
+}
+
+impl<'a> Body<'a> {
+    fn next(&mut self, ty: Scalar) -> Result<f64> {
+        let truncated = || invalid("PLY file ends early".to_string());
+        if self.format == Format::Ascii {
+            while self.pos < self.data.len() && self.data[self.pos].is_ascii_whitespace() {
+                self.pos += 1;
+            }
+            let start = self.pos;
+            while self.pos < self.data.len() && !self.data[self.pos].is_ascii_whitespace() {
+                self.pos += 1;
+            }
+            if start == self.pos {
+                return Err(truncated());
+            }
+            let token = String::from_utf8_lossy(&self.data[start..self.pos]);
+            return token
+                .parse()
+                .map_err(|_| invalid(format!("bad PLY value {}", token)));
+        }
+
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + ty.size())
+            .ok_or_else(truncated)?;
+        self.pos += ty.size();
+        let f = self.format;
+        Ok(match ty {
+            Scalar::I8 => bytes[0] as i8 as f64,
+            Scalar::U8 => bytes[0] as f64,
+            Scalar::I16 => i16::from_le_bytes(array(bytes, f)) as f64,
+            Scalar::U16 => u16::from_le_bytes(array(bytes, f)) as f64,
+            Scalar::I32 => i32::from_le_bytes(array(bytes, f)) as f64,
+            Scalar::U32 => u32::from_le_bytes(array(bytes, f)) as f64,
+            Scalar::F32 => f32::from_le_bytes(array(bytes, f)) as f64,
+            Scalar::F64 => f64::from_le_bytes(array(bytes, f)),
+        })
+    }
+
+    /// One element's properties, the items of any lists one after another.
+    fn row(&mut self, element: &Element) -> Result<Vec<Vec<f64>>> {
+        let mut row = Vec::with_capacity(element.properties.len());
+        for property in &element.properties {
+            row.push(match property {
+                Property::Scalar(_, ty) => vec![self.next(*ty)?],
+                Property::List(_, count, ty) => {
+                    let n = self.next(*count)? as usize;
+                    (0..n).map(|_| self.next(*ty)).collect::<Result<_>>()?
+                }
+            });
+        }
+        Ok(row)
+    }
+}
+
+/// A mesh or point cloud read from a PLY file, ASCII or binary, with the
+/// normals and colors of its vertices if it has them.
+pub struct Ply {
+    pub positions: Vec<Point3>,
+    pub normals: Option<Vec<Vec3>>,
+    pub colors: Option<Vec<Color3>>,
+    pub faces: Vec<Vec<usize>>,
+}
+
+impl Ply {
+    pub fn load(filename: &str) -> Result<Self> {
+        Self::parse(&std::fs::read(filename)?)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut pos = 0;
+        let mut next_line = || {
+            if pos >= data.len() {
+                return None;
+            }
+            let start = pos;
+            while pos < data.len() && data[pos] != b'\n' {
+                pos += 1;
+            }
+            pos = (pos + 1).min(data.len());
+            Some(
+                String::from_utf8_lossy(&data[start..pos])
+                    .trim()
+                    .to_string(),
+            )
+        };
+
+        if next_line().as_deref() != Some("ply") {
+            return Err(invalid("not a PLY file".to_string()));
+        }
+        let mut format = None;
+        let mut elements: Vec<Element> = Vec::new();
+        loop {
+            let line = next_line().ok_or_else(|| invalid("PLY header has no end".to_string()))?;
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["end_header"] => break,
+                ["format", name, _] => {
+                    format = Some(match *name {
+                        "ascii" => Format::Ascii,
+                        "binary_little_endian" => Format::LittleEndian,
+                        "binary_big_endian" => Format::BigEndian,
+                        _ => return Err(invalid(format!("unknown PLY format {}", name))),
+                    })
+                }
+                ["element", name, count] => elements.push(Element {
+                    name: name.to_string(),
+                    count: count
+                        .parse()
+                        .map_err(|_| invalid(format!("bad element count {}", count)))?,
+                    properties: Vec::new(),
+                }),
+                ["property", "list", count, ty, name] => elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("property before any element".to_string()))?
+                    .properties
+                    .push(Property::List(
+                        name.to_string(),
+                        Scalar::parse(count)?,
+                        Scalar::parse(ty)?,
+                    )),
+                ["property", ty, name] => elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("property before any element".to_string()))?
+                    .properties
+                    .push(Property::Scalar(name.to_string(), Scalar::parse(ty)?)),
+                ["comment", ..] | ["obj_info", ..] | [] => {}
+                _ => return Err(invalid(format!("bad PLY header line {}", line))),
+            }
+        }
+        let format = format.ok_or_else(|| invalid("PLY header has no format".to_string()))?;
+
+        let mut ply = Self {
+            positions: Vec::new(),
+            normals: None,
+            colors: None,
+            faces: Vec::new(),
+        };
+        let mut body = Body { format, data, pos };
+        for element in &elements {
+            let find = |names: &[&str]| {
+                element
+                    .properties
+                    .iter()
+                    .position(|property| names.contains(&property.name()))
+            };
+            match element.name.as_str() {
+                "vertex" => {
+                    let position = [find(&["x"]), find(&["y"]), find(&["z"])];
+                    let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+                    let color = [
+                        find(&["red", "r", "diffuse_red"]),
+                        find(&["green", "g", "diffuse_green"]),
+                        find(&["blue", "b", "diffuse_blue"]),
+                    ];
+                    let [Some(x), Some(y), Some(z)] = position else {
+                        return Err(invalid("PLY vertices have no position".to_string()));
+                    };
+                    let normal = match normal {
+                        [Some(x), Some(y), Some(z)] => Some([x, y, z]),
+                        _ => None,
+                    };
+                    let color = match color {
+                        [Some(r), Some(g), Some(b)] => Some([r, g, b].map(|i| {
+                            let scale = match element.properties[i] {
+                                Property::Scalar(_, ty) => ty.color_scale(),
+                                Property::List(..) => 1.,
+                            };
+                            (i, scale)
+                        })),
+                        _ => None,
+                    };
+
+                    let mut normals = Vec::new();
+                    let mut colors = Vec::new();
+                    for _ in 0..element.count {
+                        let row = body.row(element)?;
+                        let value = |i: usize| row[i].first().copied().unwrap_or(0.);
+                        ply.positions
+                            .push(Point3::new(value(x), value(y), value(z)));
+                        if let Some([x, y, z]) = normal {
+                            normals.push(Vec3::new(value(x), value(y), value(z)));
+                        }
+                        if let Some([(r, sr), (g, sg), (b, sb)]) = color {
+                            colors.push(Color3::new(value(r) / sr, value(g) / sg, value(b) / sb));
+                        }
+                    }
+                    if normal.is_some() {
+                        ply.normals = Some(normals);
+                    }
+                    if color.is_some() {
+                        ply.colors = Some(colors);
+                    }
+                }
+                "face" => {
+                    let indices = find(&["vertex_indices", "vertex_index"])
+                        .ok_or_else(|| invalid("PLY faces have no vertex indices".to_string()))?;
+                    for _ in 0..element.count {
+                        let row = body.row(element)?;
+                        // checked here, as the cast would quietly make them 0
+                        let face: Vec<usize> = row[indices]
+                            .iter()
+                            .map(|&i| {
+                                if i >= 0. && i.fract() == 0. {
+                                    Ok(i as usize)
+                                } else {
+                                    Err(invalid(format!("bad PLY vertex index {}", i)))
+                                }
+                            })
+                            .collect::<Result<_>>()?;
+                        // valid PLY, but no surface to cut into triangles
+                        if face.len() >= 3 {
+                            ply.faces.push(face);
+                        }
+                    }
+                }
+                _ => {
+                    for _ in 0..element.count {
+                        body.row(element)?;
+                    }
+                }
+            }
+        }
+
+        let vertices = ply.positions.len();
+        if ply.faces.iter().flatten().any(|&i| i >= vertices) {
+            return Err(invalid("PLY face refers to a missing vertex".to_string()));
+        }
+        Ok(ply)
+    }
+
+    /// The faces as a `Mesh`, e.g. to be subdivided.
+    pub fn mesh(&self) -> Mesh {
+        Mesh::new(self.positions.clone(), self.faces.clone())
+    }
+
+    /// The faces cut into triangles, with `mat_ptr` for each triangle or,
+    /// without one, a `Lambertian` blending the vertex colors. Shaded with
+    /// the file's normals, or smooth ones if it has none.
+    pub fn triangles(&self, mat_ptr: Option<Arc<dyn Material + Send + Sync>>) -> BVH {
+        let normals = match &self.normals {
+            Some(normals) => normals
+                .iter()
+                .map(|n| if n.near_zero() { *n } else { n.unit() })
+                .collect(),
+            None => self.mesh().vertex_normals(),
+        };
+        let mut triangles = HittableList::new();
+        for face in &self.faces {
+            for i in 1..face.len().saturating_sub(1) {
+                let (a, b, c) = (face[0], face[i], face[i + 1]);
+                // repeated vertices, common in scans, leave nothing to hit
+                let (pa, pb, pc) = (self.positions[a], self.positions[b], self.positions[c]);
+                if Vec3::cross(pb - pa, pc - pa).squared_length() == 0. {
+                    continue;
+                }
+                let mat_ptr = mat_ptr.clone().unwrap_or_else(|| {
+                    let color = |v: usize| self.color(v);
+                    Arc::new(Lambertian::new_texture(Arc::new(VertexColors::new(
+                        color(a),
+                        color(b),
+                        color(c),
+                    ))))
+                });
+                triangles.add(Arc::new(
+                    Triangle::new(pa, pb, pc, mat_ptr)
+                        .with_vertex_normals(normals[a], normals[b], normals[c]),
+                ));
+            }
+        }
+        BVH::new(&triangles, 0., 0.)
+    }
+
+    /// Every vertex as a sphere of `radius`, for point clouds; the material
+    /// is chosen as in `triangles`.
+    pub fn splats(&self, radius: f64, mat_ptr: Option<Arc<dyn Material + Send + Sync>>) -> BVH {
+        let mut splats = HittableList::new();
+        for (v, &p) in self.positions.iter().enumerate() {
+            let mat_ptr = mat_ptr
+                .clone()
+                .unwrap_or_else(|| Arc::new(Lambertian::new(self.color(v))));
+            splats.add(Arc::new(Sphere::new(p, radius, mat_ptr)));
+        }
+        BVH::new(&splats, 0., 0.)
+    }
+
+    /// The color of vertex `v`, a light gray if the file has none.
+    fn color(&self, v: usize) -> Color3 {
+        match &self.colors {
+            Some(colors) => colors[v],
+            None => Color3::ones() * 0.8,
+        }
+    }
+}
+
+#[test]
+fn test_ply_parse() {
+    use super::{HitRecord, Hittable};
+    use crate::ray::Ray;
+
+    let ascii = b"ply
+format ascii 1.0
+comment a square in two triangles
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 2
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 255
+3 0 1 2
+3 0 2 3
+";
+    let ply = Ply::parse(ascii).unwrap();
+    assert_eq!(ply.positions[2], Point3::new(1., 1., 0.));
+    assert_eq!(ply.colors.as_ref().unwrap()[1], Color3::new(0., 1., 0.));
+    assert_eq!(ply.faces, vec![vec![0, 1, 2], vec![0, 2, 3]]);
+    assert!(ply.normals.is_none());
+    for bad in ["3 0 2 -1", "3 0 2 2.5"] {
+        let text = String::from_utf8_lossy(ascii).replace("3 0 2 3", bad);
+        assert!(Ply::parse(text.as_bytes()).is_err());
+    }
+
+    // an empty face is dropped, and one with a repeated vertex is skipped
+    let text =
+        String::from_utf8_lossy(ascii).replace("element face 2", "element face 4") + "0\n3 0 0 1\n";
+    let scan = Ply::parse(text.as_bytes()).unwrap();
+    assert_eq!(scan.faces.len(), 3);
+    let mut rec = HitRecord::new();
+    let r = Ray::new(Point3::new(0.5, 0.25, 1.), Vec3::new(0., 0., -1.), 0.);
+    assert!(scan.triangles(None).hit(&r, 0.001, f64::INFINITY, &mut rec));
+
+    // the same vertices as a big-endian point cloud with normals
+    let mut binary = b"ply
+format binary_big_endian 1.0
+element vertex 4
+property double x
+property double y
+property double z
+property float nx
+property float ny
+property float nz
+end_header
+"
+    .to_vec();
+    for p in &ply.positions {
+        for a in 0..3 {
+            binary.extend_from_slice(&p[a].to_be_bytes());
+        }
+        for n in [0f32, 0., 1.] {
+            binary.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+    let cloud = Ply::parse(&binary).unwrap();
+    assert_eq!(cloud.positions, ply.positions);
+    assert_eq!(cloud.normals.unwrap()[3], Vec3::new(0., 0., 1.));
+    assert!(cloud.faces.is_empty());
+
+    binary.truncate(binary.len() - 1);
+    assert!(Ply::parse(&binary).is_err());
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use super::{
+    aabb::AABB, area_pdf_value, random_to_surface, uniform_surface_pdf, HitRecord, Hittable,
+    Material, SurfaceSample,
+};
+use crate::{ray::Ray, rtweekend::random_double_2d, Point3, Vec3};
+
+/// A parallelogram with corner `q` and edges `u` and `v`, in any orientation.
+/// Its UVs run along the edges.
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    w: Vec3, // turns the plane's cross products into edge coordinates
+    area: f64,
+    mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, mat_ptr: Arc<dyn Material + Send + Sync>) -> Self {
+        let n = Vec3::cross(u, v);
+        // parallel edges leave no normal, and a zero one that no ray hits
+        let degenerate = n.squared_length() == 0.;
+        Self {
+            q,
+            u,
+            v,
+            normal: if degenerate { n } else { n.unit() },
+            w: if degenerate {
+                n
+            } else {
+                n / n.squared_length()
+            },
+            area: n.length(),
+            mat_ptr,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let denom = self.normal * r.direction();
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+        let t = (self.q - r.origin()) * self.normal / denom;
+        if t < t_min || t > t_max {
+            return false;
+        }
+
+        let p = r.at(t);
+        let planar = p - self.q;
+        let alpha = self.w * Vec3::cross(planar, self.v);
+        let beta = self.w * Vec3::cross(self.u, planar);
+        if !(0. ..=1.).contains(&alpha) || !(0. ..=1.).contains(&beta) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = alpha;
+        rec.v = beta;
+        rec.set_face_normal(r, self.normal);
+        rec.mat_ptr = Some(self.mat_ptr.clone());
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut AABB) -> bool {
+        *output_box = AABB::from_points(&[
+            self.q,
+            self.q + self.u,
+            self.q + self.v,
+            self.q + self.u + self.v,
+        ]);
+        true
+    }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        area_pdf_value(self, self.area, o, v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        random_to_surface(self, o)
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let (s, t) = random_double_2d();
+        Some(SurfaceSample {
+            p: self.q + s * self.u + t * self.v,
+            normal: self.normal,
+            pdf: 1. / self.area,
+        })
+    }
+
+    fn surface_pdf(&self, o: &Point3, v: &Vec3) -> f64 {
+        uniform_surface_pdf(self, self.area, o, v)
+    }
+}
@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use super::{aabb::AABB, HitRecord, Hittable, Material};
+use crate::{ray::Ray, Point3, Vec3};
+
+/// A signed distance function: negative inside, positive outside, and never
+/// more than the true distance to the surface.
+pub type Distance = Arc<dyn Fn(Point3) -> f64 + Send + Sync>;
+
+/// A shape given by a signed distance function, rendered by sphere tracing.
+/// The distance function has to stay inside the box given for the `BVH`.
+/// There is no natural parameterization, so `u` and `v` are always 0.
+pub struct Sdf {
+    distance: Distance,
+    bound: AABB,
+    epsilon: f64,
+    max_steps: usize,
+    mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl Sdf {
+    pub fn new(
+        distance: Distance,
+        minimum: Point3,
+        maximum: Point3,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self {
+            distance,
+            bound: AABB::new(minimum, maximum),
+            epsilon: 1e-4,
+            max_steps: 256,
+            mat_ptr,
+        }
+    }
+
+    /// How close to the surface counts as a hit.
+    #[allow(dead_code)]
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// How many steps a ray may take before it is taken to have missed.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// The gradient of the distance by central differences.
+    fn normal(&self, p: Point3) -> Vec3 {
+        let h = self.epsilon;
+        let f = &self.distance;
+        Vec3::new(
+            f(p + Vec3::new(h, 0., 0.)) - f(p - Vec3::new(h, 0., 0.)),
+            f(p + Vec3::new(0., h, 0.)) - f(p - Vec3::new(0., h, 0.)),
+            f(p + Vec3::new(0., 0., h)) - f(p - Vec3::new(0., 0., h)),
+        )
+        .unit()
+    }
+}
+
+impl Hittable for Sdf {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let (t0, t1) = match self.bound.clip(r, t_min, t_max) {
+            Some(interval) => interval,
+            None => return false,
+        };
+        let speed = r.direction().length();
+        let mut t = t0;
+        // a ray leaving the surface it just hit starts within epsilon of it,
+        // while one entering the box can only be meeting the surface
+        let mut left_surface = t0 > t_min;
+        for _ in 0..self.max_steps {
+            let d = (self.distance)(r.at(t)).abs();
+            if d < self.epsilon {
+                if left_surface {
+                    rec.t = t;
+                    rec.p = r.at(t);
+                    rec.u = 0.;
+                    rec.v = 0.;
+                    rec.set_face_normal(r, self.normal(rec.p));
+                    rec.mat_ptr = Some(self.mat_ptr.clone());
+                    return true;
+                }
+                t += self.epsilon / speed;
+            } else {
+                left_surface = true;
+                t += d / speed;
+            }
+            if t > t1 {
+                break;
+            }
+        }
+        false
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut AABB) -> bool {
+        *output_box = self.bound;
+        true
+    }
+}
+
+pub fn sphere(center: Point3, radius: f64) -> Distance {
+    Arc::new(move |p| (p - center).length() - radius)
+}
+
+/// A box of half extents `half` around `center`, rounded by `radius`.
+pub fn rounded_box(center: Point3, half: Vec3, radius: f64) -> Distance {
+    Arc::new(move |p| {
+        let q = p - center;
+        let q = Vec3::new(q.x().abs(), q.y().abs(), q.z().abs()) - half;
+        let outside = Vec3::new(q.x().max(0.), q.y().max(0.), q.z().max(0.));
+        outside.length() + q.x().max(q.y()).max(q.z()).min(0.) - radius
+    })
+}
+
+/// The union of `a` and `b`, blended where they are within `k` of each other.
+pub fn smooth_union(a: Distance, b: Distance, k: f64) -> Distance {
+    Arc::new(move |p| {
+        let (da, db) = (a(p), b(p));
+        let h = (0.5 + 0.5 * (db - da) / k).clamp(0., 1.);
+        db + (da - db) * h - k * h * (1. - h)
+    })
+}
+
+/// The Mandelbulb fractal of `power` around `center`, scaled to `size`.
+pub fn mandelbulb(center: Point3, size: f64, power: f64, iterations: usize) -> Distance {
+    Arc::new(move |p| {
+        let c = (p - center) / size;
+        let mut z = c;
+        let mut dr = 1.;
+        let mut r = 0.;
+        for _ in 0..iterations {
+            r = z.length();
+            if r > 2. {
+                break;
+            }
+            let theta = (z.z() / r).acos() * power;
+            let phi = z.y().atan2(z.x()) * power;
+            dr = r.powf(power - 1.) * power * dr + 1.;
+            let direction = Vec3::new(
+                theta.sin() * phi.cos(),
+                theta.sin() * phi.sin(),
+                theta.cos(),
+            );
+            z = r.powf(power) * direction + c;
+        }
+        0.5 * r.ln() * r / dr * size
+    })
+}
+
+#[test]
+fn test_sdf_sphere() {
+    use crate::material::Lambertian;
+    use crate::Color3;
+
+    let ball = Sdf::new(
+        sphere(Point3::zero(), 1.),
+        -Point3::ones(),
+        Point3::ones(),
+        Arc::new(Lambertian::new(Color3::ones())),
+    );
+    let mut rec = HitRecord::new();
+
+    let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 2.), 0.);
+    assert!(ball.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 2.).abs() < 1e-3);
+    assert!((rec.normal - Vec3::new(0., 0., -1.)).length() < 1e-3);
+
+    // leaving through the far side from the near one
+    let r = Ray::new(rec.p, Vec3::new(0., 0., 1.), 0.);
+    assert!(ball.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.p.z() - 1.).abs() < 1e-3);
+    assert!(!rec.front_face);
+}
@@ -3,7 +3,7 @@ use std::f64::INFINITY;
 use std::sync::Arc;
 
 use crate::hittable::aabb::AABB;
-use crate::hittable::{HitRecord, Hittable};
+use crate::hittable::{uniform_surface_pdf, HitRecord, Hittable, SurfaceSample};
 use crate::material::Material;
 use crate::pdf::onb::Onb;
 use crate::pdf::random_to_sphere;
@@ -94,6 +94,19 @@ impl Hittable for Sphere {
         let uvw = Onb::build_from_w(direction);
         uvw.local_vec(&random_to_sphere(self.radius, distance_squared))
     }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let normal = Vec3::random_unit_vector();
+        Some(SurfaceSample {
+            p: self.center + self.radius * normal,
+            normal,
+            pdf: 1. / (4. * PI * self.radius * self.radius),
+        })
+    }
+
+    fn surface_pdf(&self, o: &Point3, v: &Vec3) -> f64 {
+        uniform_surface_pdf(self, 4. * PI * self.radius * self.radius, o, v)
+    }
 }
 
 #[test]
@@ -104,3 +117,21 @@ fn test_get_uv() {
     assert_eq!(u, 0.75);
     assert_eq!(v, 0.5);
 }
+
+#[test]
+fn test_surface_pdf_at_point() {
+    use crate::material::Lambertian;
+    use crate::Color3;
+
+    let sphere = Sphere::new(
+        Point3::zero(),
+        1.,
+        Arc::new(Lambertian::new(Color3::ones())),
+    );
+    let o = Point3::new(0., 0., 5.);
+    let area = 4. * PI;
+    // points on either side of the sphere, but not its center
+    assert_eq!(sphere.surface_pdf(&o, &Vec3::new(0., 0., -4.)), 1. / area);
+    assert_eq!(sphere.surface_pdf(&o, &Vec3::new(0., 0., -6.)), 1. / area);
+    assert_eq!(sphere.surface_pdf(&o, &Vec3::new(0., 0., -5.)), 0.);
+}
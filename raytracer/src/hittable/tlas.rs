@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use super::{aabb::AABB, bvh::count_node_visit, hittable_list::HittableList, HitRecord, Hittable};
+use crate::{ray::Ray, Point3};
+
+#[derive(Clone, Copy)]
+enum NodeKind {
+    Leaf(usize), // index into `objects`
+    Inner(usize, usize),
+}
+
+#[derive(Clone, Copy)]
+struct Node {
+    bbox: AABB,
+    kind: NodeKind,
+}
+
+// deep enough for any tree split at the median
+const STACK_SIZE: usize = 64;
+
+/// A top-level BVH over whole objects, each usually an `Instance` or
+/// `Animated` placement of its own bottom-level `BVH`. When objects move
+/// between frames its boxes can be refit to the new shutter interval, which
+/// is far cheaper than building it again; once refitting has let the tree
+/// grow too loose, it is rebuilt instead.
+#[derive(Clone)]
+pub struct Tlas {
+    objects: Vec<Arc<dyn Hittable + Send + Sync>>,
+    nodes: Vec<Node>, // children always come after their parent
+    built_cost: f64,  // the summed node area right after the last build
+    rebuild_ratio: f64,
+}
+
+fn bounding_box(object: &Arc<dyn Hittable + Send + Sync>, time0: f64, time1: f64) -> AABB {
+    let mut bbox = AABB::new(Point3::zero(), Point3::zero());
+    if !object.bounding_box(time0, time1, &mut bbox) {
+        panic!("No bounding box in Tlas constructor!");
+    }
+    bbox
+}
+
+fn surface_area(bbox: &AABB) -> f64 {
+    let d = bbox.max() - bbox.min();
+    2. * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+}
+
+impl Tlas {
+    pub fn new(list: &HittableList, time0: f64, time1: f64) -> Self {
+        let mut tlas = Self {
+            objects: list.objects.clone(),
+            nodes: Vec::new(),
+            built_cost: 0.,
+            rebuild_ratio: 2.,
+        };
+        tlas.rebuild(time0, time1);
+        tlas
+    }
+
+    /// How much looser than when built the tree may get before `update`
+    /// rebuilds it rather than refitting.
+    #[allow(dead_code)]
+    pub fn with_rebuild_ratio(mut self, rebuild_ratio: f64) -> Self {
+        self.rebuild_ratio = rebuild_ratio;
+        self
+    }
+
+    /// Builds the tree from scratch around where the objects are during
+    /// `time0..time1`, splitting at the median along the widest axis.
+    pub fn rebuild(&mut self, time0: f64, time1: f64) {
+        let boxes: Vec<AABB> = self
+            .objects
+            .iter()
+            .map(|object| bounding_box(object, time0, time1))
+            .collect();
+        let mut indices: Vec<usize> = (0..self.objects.len()).collect();
+        self.nodes.clear();
+        if !indices.is_empty() {
+            self.build(&boxes, &mut indices);
+        }
+        self.built_cost = self.cost();
+    }
+
+    fn build(&mut self, boxes: &[AABB], indices: &mut [usize]) -> usize {
+        let index = self.nodes.len();
+        if indices.len() == 1 {
+            self.nodes.push(Node {
+                bbox: boxes[indices[0]],
+                kind: NodeKind::Leaf(indices[0]),
+            });
+            return index;
+        }
+
+        let center = |i: usize| (boxes[i].min() + boxes[i].max()) / 2.;
+        let mut low = center(indices[0]);
+        let mut high = low;
+        for &i in indices.iter() {
+            let c = center(i);
+            for a in 0..3 {
+                low[a] = low[a].min(c[a]);
+                high[a] = high[a].max(c[a]);
+            }
+        }
+        let extent = high - low;
+        let axis = if extent.x() > extent.y() && extent.x() > extent.z() {
+            0
+        } else if extent.y() > extent.z() {
+            1
+        } else {
+            2
+        };
+        indices.sort_by(|&a, &b| center(a)[axis].partial_cmp(&center(b)[axis]).unwrap());
+
+        // placeholder until the children are known
+        self.nodes.push(Node {
+            bbox: boxes[indices[0]],
+            kind: NodeKind::Leaf(indices[0]),
+        });
+        let mid = indices.len() / 2;
+        let (left, right) = indices.split_at_mut(mid);
+        let left = self.build(boxes, left);
+        let right = self.build(boxes, right);
+        self.nodes[index] = Node {
+            bbox: AABB::surrounding_box(&self.nodes[left].bbox, &self.nodes[right].bbox),
+            kind: NodeKind::Inner(left, right),
+        };
+        index
+    }
+
+    /// Moves every box to where its objects are during `time0..time1`,
+    /// keeping the shape of the tree.
+    pub fn refit(&mut self, time0: f64, time1: f64) {
+        for i in (0..self.nodes.len()).rev() {
+            self.nodes[i].bbox = match self.nodes[i].kind {
+                NodeKind::Leaf(object) => bounding_box(&self.objects[object], time0, time1),
+                NodeKind::Inner(left, right) => {
+                    AABB::surrounding_box(&self.nodes[left].bbox, &self.nodes[right].bbox)
+                }
+            };
+        }
+    }
+
+    /// Refits the tree to `time0..time1`, or rebuilds it if that would leave
+    /// it much looser than it was when last built.
+    pub fn update(&mut self, time0: f64, time1: f64) {
+        self.refit(time0, time1);
+        if self.cost() > self.rebuild_ratio * self.built_cost {
+            self.rebuild(time0, time1);
+        }
+    }
+
+    /// The summed surface area of all nodes, roughly how many boxes a ray
+    /// has to test.
+    fn cost(&self) -> f64 {
+        self.nodes.iter().map(|node| surface_area(&node.bbox)).sum()
+    }
+}
+
+impl Hittable for Tlas {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        let mut stack = [0; STACK_SIZE];
+        let mut len = 1;
+        let mut closest_so_far = t_max;
+        let mut hit_anything = false;
+        while len > 0 {
+            len -= 1;
+            let node = &self.nodes[stack[len]];
+            count_node_visit();
+            if !node.bbox.hit(r, t_min, closest_so_far) {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf(object) => {
+                    if self.objects[object].hit(r, t_min, closest_so_far, rec) {
+                        hit_anything = true;
+                        closest_so_far = rec.t;
+                    }
+                }
+                NodeKind::Inner(left, right) => {
+                    stack[len] = right;
+                    stack[len + 1] = left;
+                    len += 2;
+                }
+            }
+        }
+        hit_anything
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut AABB) -> bool {
+        match self.nodes.first() {
+            Some(root) => {
+                *output_box = root.bbox;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[test]
+fn test_tlas_refit() {
+    use crate::animation::Track;
+    use crate::hittable::{animated::Animated, sphere::Sphere};
+    use crate::material::Lambertian;
+    use crate::{Color3, Vec3};
+
+    let mat = Arc::new(Lambertian::new(Color3::ones()));
+    let mut list = HittableList::new();
+    list.add(Arc::new(Sphere::new(
+        Point3::new(0., 0., -5.),
+        1.,
+        mat.clone(),
+    )));
+    list.add(Arc::new(Animated::new(
+        Arc::new(Sphere::new(Point3::zero(), 1., mat)),
+        Track::new(0., Vec3::zero()).key(1., Vec3::new(10., 0., 0.)),
+        Track::constant(0.),
+    )));
+    let mut tlas = Tlas::new(&list, 0., 0.);
+    let mut rec = HitRecord::new();
+
+    // built around the start, so the sphere's later place is outside every box
+    let r = Ray::new(Point3::new(10., 0., 5.), Vec3::new(0., 0., -1.), 1.);
+    assert!(!tlas.hit(&r, 0.001, f64::INFINITY, &mut rec));
+
+    tlas.refit(1., 1.);
+    assert!(tlas.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 4.).abs() < 1e-9);
+
+    let r = Ray::new(Point3::new(0., 0., 5.), Vec3::new(0., 0., -1.), 1.);
+    assert!(tlas.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 9.).abs() < 1e-9);
+}
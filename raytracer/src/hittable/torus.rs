@@ -0,0 +1,226 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::{
+    aabb::AABB, area_pdf_value, azimuth, random_to_surface, uniform_surface_pdf, HitRecord,
+    Hittable, Material, SurfaceSample,
+};
+use crate::{pdf::onb::Onb, ray::Ray, rtweekend::random_double_unit, Point3, Vec3};
+
+/// A torus around `axis`: a tube of `minor_radius` swept along a circle of
+/// `major_radius`. `u` goes around the axis and `v` around the tube.
+pub struct Torus {
+    center: Point3,
+    major_radius: f64,
+    minor_radius: f64,
+    uvw: Onb, // w is the axis
+    mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point3,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+            uvw: Onb::build_from_w(axis),
+            mat_ptr,
+        }
+    }
+
+    fn area(&self) -> f64 {
+        4. * PI * PI * self.major_radius * self.minor_radius
+    }
+
+    fn normal(&self, p: &Vec3) -> Vec3 {
+        let (r2, t2) = (self.major_radius.powi(2), self.minor_radius.powi(2));
+        let k = p.squared_length() + r2 - t2;
+        (4. * k * *p - 8. * r2 * Vec3::new(p.x(), p.y(), 0.)).unit()
+    }
+}
+
+impl Hittable for Torus {
+    /// Solves the quartic along the ray inside the bounding sphere. Its roots
+    /// are isolated between the roots of its derivatives, so a ray that only
+    /// grazes the tube is found however short the stretch it spends inside.
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let o = self.uvw.to_local(&(r.origin() - self.center));
+        let d = self.uvw.to_local(&r.direction());
+
+        let bound = (self.major_radius + self.minor_radius) * 1.001;
+        let a = d.squared_length();
+        let half_b = o * d;
+        let c = o.squared_length() - bound * bound;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0. {
+            return false;
+        }
+        let sqrtd = discriminant.sqrt();
+        let t0 = ((-half_b - sqrtd) / a).max(t_min);
+        let t1 = ((-half_b + sqrtd) / a).min(t_max);
+        if t0 >= t1 {
+            return false;
+        }
+
+        // measured from where the ray enters the sphere, to keep the
+        // coefficients small
+        let o0 = o + t0 * d;
+        let r2 = self.major_radius.powi(2);
+        let b = 2. * (o0 * d);
+        let c = o0.squared_length() + r2 - self.minor_radius.powi(2);
+        let flat = (
+            d.x() * d.x() + d.y() * d.y(),
+            o0.x() * d.x() + o0.y() * d.y(),
+        );
+        let quartic = [
+            a * a,
+            2. * a * b,
+            b * b + 2. * a * c - 4. * r2 * flat.0,
+            2. * b * c - 8. * r2 * flat.1,
+            c * c - 4. * r2 * (o0.x() * o0.x() + o0.y() * o0.y()),
+        ];
+        let found = first_root(&quartic, 0., t1 - t0).map(|s| t0 + s);
+
+        let t = match found {
+            Some(t) => t,
+            None => return false,
+        };
+        let p = o + t * d;
+        let from_ring = (p.x() * p.x() + p.y() * p.y()).sqrt() - self.major_radius;
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.u = azimuth(&p);
+        rec.v = (p.z().atan2(from_ring) + PI) / (2. * PI);
+        rec.set_face_normal(r, self.uvw.local_vec(&self.normal(&p)));
+        rec.mat_ptr = Some(self.mat_ptr.clone());
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut AABB) -> bool {
+        let ring = AABB::around_circle(self.center, self.uvw.w(), self.major_radius);
+        let tube = Vec3::ones() * self.minor_radius;
+        *output_box = AABB::new(ring.min() - tube, ring.max() + tube);
+        true
+    }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        area_pdf_value(self, self.area(), o, v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        random_to_surface(self, o)
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        // the outside of the tube has more area than the inside
+        let (big, small) = (self.major_radius, self.minor_radius);
+        let theta = loop {
+            let theta = 2. * PI * random_double_unit();
+            if random_double_unit() * (big + small) <= big + small * theta.cos() {
+                break theta;
+            }
+        };
+        let phi = 2. * PI * random_double_unit();
+        let normal = Vec3::new(
+            theta.cos() * phi.cos(),
+            theta.cos() * phi.sin(),
+            theta.sin(),
+        );
+        let local_p = big * Vec3::new(phi.cos(), phi.sin(), 0.) + small * normal;
+        Some(SurfaceSample {
+            p: self.center + self.uvw.local_vec(&local_p),
+            normal: self.uvw.local_vec(&normal),
+            pdf: 1. / self.area(),
+        })
+    }
+
+    fn surface_pdf(&self, o: &Point3, v: &Vec3) -> f64 {
+        uniform_surface_pdf(self, self.area(), o, v)
+    }
+}
+
+fn evaluate(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().fold(0., |sum, c| sum * x + c)
+}
+
+/// The smallest root in `(lo, hi]` of the polynomial with `coefficients`,
+/// highest power first. Between consecutive roots of the derivative the
+/// polynomial is monotonic, so each of those stretches holds at most one root.
+fn first_root(coefficients: &[f64], lo: f64, hi: f64) -> Option<f64> {
+    let degree = coefficients.len() - 1;
+    if degree == 0 {
+        return None;
+    }
+    let derivative: Vec<f64> = coefficients[..degree]
+        .iter()
+        .enumerate()
+        .map(|(i, c)| c * (degree - i) as f64)
+        .collect();
+
+    let mut start = lo;
+    loop {
+        let end = first_root(&derivative, start, hi)
+            .filter(|&end| end > start)
+            .unwrap_or(hi);
+        let inside = evaluate(coefficients, start) <= 0.;
+        if (evaluate(coefficients, end) <= 0.) != inside {
+            let (mut lo, mut hi) = (start, end);
+            for _ in 0..60 {
+                let mid = (lo + hi) / 2.;
+                if (evaluate(coefficients, mid) <= 0.) == inside {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            return Some(hi);
+        }
+        if end >= hi {
+            return None;
+        }
+        start = end;
+    }
+}
+
+#[test]
+fn test_torus_hit() {
+    use crate::material::Lambertian;
+    use crate::Color3;
+
+    let torus = Torus::new(
+        Point3::zero(),
+        Vec3::new(0., 1., 0.),
+        2.,
+        0.5,
+        Arc::new(Lambertian::new(Color3::ones())),
+    );
+    let mut rec = HitRecord::new();
+
+    // through the hole
+    let r = Ray::new(Point3::new(0., 5., 0.), Vec3::new(0., -1., 0.), 0.);
+    assert!(!torus.hit(&r, 0.001, f64::INFINITY, &mut rec));
+
+    // onto the top of the tube
+    let r = Ray::new(Point3::new(2., 5., 0.), Vec3::new(0., -1., 0.), 0.);
+    assert!(torus.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 4.5).abs() < 1e-9);
+    assert!((rec.normal - Vec3::new(0., 1., 0.)).length() < 1e-6);
+
+    // across, meeting the outside of the tube first
+    let r = Ray::new(Point3::new(-5., 0., 0.), Vec3::new(1., 0., 0.), 0.);
+    assert!(torus.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - 2.5).abs() < 1e-9);
+
+    // grazing the outer equator, inside the tube for under a ten-thousandth
+    let x = 2.5 - 1e-9;
+    let r = Ray::new(Point3::new(x, -5., 0.), Vec3::new(0., 1., 0.), 0.);
+    assert!(torus.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert!((rec.t - (5. - (0.25 - (x - 2.).powi(2)).sqrt())).abs() < 1e-6);
+    let r = Ray::new(Point3::new(2.5 + 1e-9, -5., 0.), Vec3::new(0., 1., 0.), 0.);
+    assert!(!torus.hit(&r, 0.001, f64::INFINITY, &mut rec));
+}
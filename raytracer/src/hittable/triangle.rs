@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use super::{
+    aabb::AABB, area_pdf_value, random_to_surface, uniform_surface_pdf, HitRecord, Hittable,
+    Material, SurfaceSample,
+};
+use crate::{ray::Ray, rtweekend::random_double_2d, Point3, Vec3};
+
+/// A triangle with vertices `a`, `b` and `c`, facing the way they wind
+/// counterclockwise. Its UVs are the barycentric weights of `b` and `c`
+/// unless texture coordinates are given at the vertices.
+pub struct Triangle {
+    a: Point3,
+    b: Point3,
+    c: Point3,
+    normal: Vec3,
+    vertex_normals: Option<[Vec3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    w: Vec3, // turns the plane's cross products into barycentric weights
+    area: f64,
+    mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl Triangle {
+    pub fn new(a: Point3, b: Point3, c: Point3, mat_ptr: Arc<dyn Material + Send + Sync>) -> Self {
+        let n = Vec3::cross(b - a, c - a);
+        // collinear vertices leave no normal, and a zero one that no ray hits
+        let degenerate = n.squared_length() == 0.;
+        Self {
+            a,
+            b,
+            c,
+            normal: if degenerate { n } else { n.unit() },
+            vertex_normals: None,
+            uvs: None,
+            w: if degenerate {
+                n
+            } else {
+                n / n.squared_length()
+            },
+            area: n.length() / 2.,
+            mat_ptr,
+        }
+    }
+
+    /// Shades with normals interpolated from these at the vertices, as for a
+    /// triangle cut from a smooth surface. Which side is the front still
+    /// follows the winding.
+    pub fn with_vertex_normals(mut self, na: Vec3, nb: Vec3, nc: Vec3) -> Self {
+        self.vertex_normals = Some([na, nb, nc]);
+        self
+    }
+
+    /// Texture coordinates at the vertices, interpolated across the face.
+    pub fn with_uvs(mut self, ua: (f64, f64), ub: (f64, f64), uc: (f64, f64)) -> Self {
+        self.uvs = Some([ua, ub, uc]);
+        self
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let denom = self.normal * r.direction();
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+        let t = (self.a - r.origin()) * self.normal / denom;
+        if t < t_min || t > t_max {
+            return false;
+        }
+
+        let p = r.at(t);
+        let planar = p - self.a;
+        let beta = self.w * Vec3::cross(planar, self.c - self.a);
+        let gamma = self.w * Vec3::cross(self.b - self.a, planar);
+        if beta < 0. || gamma < 0. || beta + gamma > 1. {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        (rec.u, rec.v) = match self.uvs {
+            Some([ua, ub, uc]) => {
+                let alpha = 1. - beta - gamma;
+                (
+                    alpha * ua.0 + beta * ub.0 + gamma * uc.0,
+                    alpha * ua.1 + beta * ub.1 + gamma * uc.1,
+                )
+            }
+            None => (beta, gamma),
+        };
+        rec.set_face_normal(r, self.normal);
+        if let Some([na, nb, nc]) = self.vertex_normals {
+            let shading = ((1. - beta - gamma) * na + beta * nb + gamma * nc).unit();
+            rec.normal = if rec.front_face { shading } else { -shading };
+        }
+        rec.mat_ptr = Some(self.mat_ptr.clone());
+        true
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut AABB) -> bool {
+        *output_box = AABB::from_points(&[self.a, self.b, self.c]);
+        true
+    }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        area_pdf_value(self, self.area, o, v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        random_to_surface(self, o)
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let (s, t) = random_double_2d();
+        let su = s.sqrt();
+        let (beta, gamma) = (su * (1. - t), su * t);
+        Some(SurfaceSample {
+            p: self.a + beta * (self.b - self.a) + gamma * (self.c - self.a),
+            normal: self.normal,
+            pdf: 1. / self.area,
+        })
+    }
+
+    fn surface_pdf(&self, o: &Point3, v: &Vec3) -> f64 {
+        uniform_surface_pdf(self, self.area, o, v)
+    }
+}
+
+#[test]
+fn test_triangle_uv() {
+    use crate::material::Lambertian;
+    use crate::Color3;
+
+    let triangle = Triangle::new(
+        Point3::zero(),
+        Point3::new(1., 0., 0.),
+        Point3::new(0., 1., 0.),
+        Arc::new(Lambertian::new(Color3::ones())),
+    );
+    let mut rec = HitRecord::new();
+    let r = Ray::new(Point3::new(0.25, 0.5, 1.), Vec3::new(0., 0., -1.), 0.);
+    assert!(triangle.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert_eq!((rec.u, rec.v), (0.25, 0.5));
+    assert!(rec.front_face);
+
+    let r = Ray::new(Point3::new(0.75, 0.5, 1.), Vec3::new(0., 0., -1.), 0.);
+    assert!(!triangle.hit(&r, 0.001, f64::INFINITY, &mut rec));
+
+    let line = Triangle::new(
+        Point3::zero(),
+        Point3::new(1., 0., 0.),
+        Point3::new(2., 0., 0.),
+        Arc::new(Lambertian::new(Color3::ones())),
+    );
+    let r = Ray::new(Point3::new(1., 0., 1.), Vec3::new(0., 0., -1.), 0.);
+    assert!(!line.hit(&r, 0.001, f64::INFINITY, &mut rec));
+
+    let triangle = triangle.with_uvs((0.5, 0.), (1., 0.), (0.5, 1.));
+    let r = Ray::new(Point3::new(0.25, 0.5, 1.), Vec3::new(0., 0., -1.), 0.);
+    assert!(triangle.hit(&r, 0.001, f64::INFINITY, &mut rec));
+    assert_eq!((rec.u, rec.v), (0.625, 0.5));
+}
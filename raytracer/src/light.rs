@@ -0,0 +1,176 @@
+pub mod profile;
+
+use std::sync::Arc;
+
+use crate::{
+    hittable::hittable_list::HittableList, rtweekend::degrees_to_radius, Color3, Point3, Vec3,
+};
+
+use self::profile::EmissionProfile;
+
+/// Incident lighting at a point from a single light.
+pub struct LightSample {
+    pub wi: Vec3,  // unit direction from the shading point towards the light
+    pub dist: f64, // distance to the light, infinite for directional lights
+    pub radiance: Color3,
+}
+
+/// Lights without any geometry, which can only be reached by explicit sampling.
+pub trait Light {
+    fn sample_li(&self, p: &Point3) -> LightSample;
+}
+
+pub struct PointLight {
+    position: Point3,
+    intensity: Color3,
+    axis: Vec3,
+    profile: Option<Arc<EmissionProfile>>,
+}
+
+impl PointLight {
+    #[allow(dead_code)]
+    pub fn new(position: Point3, intensity: Color3) -> Self {
+        Self {
+            position,
+            intensity,
+            axis: Vec3::new(0., -1., 0.),
+            profile: None,
+        }
+    }
+
+    /// A point light whose intensity varies with the angle to `axis`.
+    pub fn new_profile(
+        position: Point3,
+        intensity: Color3,
+        axis: Vec3,
+        profile: Arc<EmissionProfile>,
+    ) -> Self {
+        Self {
+            position,
+            intensity,
+            axis: axis.unit(),
+            profile: Some(profile),
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample_li(&self, p: &Point3) -> LightSample {
+        let d = self.position - *p;
+        let dist = d.length();
+        let wi = d / dist;
+        let mut radiance = self.intensity / (dist * dist);
+        if let Some(profile) = &self.profile {
+            let cos_theta = (-wi) * self.axis;
+            radiance *= profile.value(cos_theta.clamp(-1., 1.).acos());
+        }
+        LightSample { wi, dist, radiance }
+    }
+}
+
+pub struct SpotLight {
+    position: Point3,
+    direction: Vec3,
+    intensity: Color3,
+    cos_total_width: f64,
+    cos_falloff_start: f64,
+}
+
+impl SpotLight {
+    /// `total_width` and `falloff_start` are the half-angles of the cone in degrees.
+    pub fn new(
+        position: Point3,
+        lookat: Point3,
+        intensity: Color3,
+        total_width: f64,
+        falloff_start: f64,
+    ) -> Self {
+        Self {
+            position,
+            direction: (lookat - position).unit(),
+            intensity,
+            cos_total_width: degrees_to_radius(total_width).cos(),
+            cos_falloff_start: degrees_to_radius(falloff_start).cos(),
+        }
+    }
+
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta < self.cos_total_width {
+            return 0.;
+        }
+        if cos_theta >= self.cos_falloff_start {
+            return 1.;
+        }
+        let delta =
+            (cos_theta - self.cos_total_width) / (self.cos_falloff_start - self.cos_total_width);
+        delta * delta * delta * delta
+    }
+}
+
+impl Light for SpotLight {
+    fn sample_li(&self, p: &Point3) -> LightSample {
+        let d = self.position - *p;
+        let dist = d.length();
+        let wi = d / dist;
+        let radiance = self.intensity * self.falloff((-wi) * self.direction) / (dist * dist);
+        LightSample { wi, dist, radiance }
+    }
+}
+
+/// A light infinitely far away, e.g. the sun.
+pub struct DirectionalLight {
+    wi: Vec3,
+    radiance: Color3,
+}
+
+impl DirectionalLight {
+    /// `direction` is the direction the light travels in.
+    pub fn new(direction: Vec3, radiance: Color3) -> Self {
+        Self {
+            wi: -direction.unit(),
+            radiance,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample_li(&self, _p: &Point3) -> LightSample {
+        LightSample {
+            wi: self.wi,
+            dist: f64::INFINITY,
+            radiance: self.radiance,
+        }
+    }
+}
+
+/// Everything the integrator samples explicitly: emissive geometry and delta lights.
+#[derive(Clone)]
+pub struct LightList {
+    pub area: Arc<HittableList>,
+    pub delta: Vec<Arc<dyn Light + Send + Sync>>,
+}
+
+impl LightList {
+    pub fn new(area: HittableList, delta: Vec<Arc<dyn Light + Send + Sync>>) -> Self {
+        Self {
+            area: Arc::new(area),
+            delta,
+        }
+    }
+}
+
+#[test]
+fn test_spot_falloff() {
+    let spot = SpotLight::new(
+        Point3::new(0., 10., 0.),
+        Point3::zero(),
+        Color3::ones() * 100.,
+        30.,
+        20.,
+    );
+    let inside = spot.sample_li(&Point3::zero());
+    assert_eq!(inside.radiance, Color3::ones());
+    assert_eq!(inside.wi, Vec3::new(0., 1., 0.));
+    let outside = spot.sample_li(&Point3::new(10., 0., 0.));
+    assert_eq!(outside.radiance, Color3::zero());
+}
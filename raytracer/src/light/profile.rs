@@ -0,0 +1,52 @@
+use crate::rtweekend::degrees_to_radius;
+
+/// Angular emission profile in the spirit of IES photometric data: relative
+/// intensities measured at evenly spaced angles away from the light's axis.
+pub struct EmissionProfile {
+    intensities: Vec<f64>,
+    max_angle: f64,
+}
+
+impl EmissionProfile {
+    /// `candela` holds samples from 0 to `max_angle` degrees (inclusive); the
+    /// values are normalized so that the brightest direction has weight one.
+    pub fn new(candela: Vec<f64>, max_angle: f64) -> Self {
+        if candela.is_empty() {
+            panic!("Emission profile needs at least one sample!");
+        }
+        let peak = candela.iter().cloned().fold(0., f64::max);
+        let intensities = if peak > 0. {
+            candela.iter().map(|c| c / peak).collect()
+        } else {
+            candela
+        };
+        Self {
+            intensities,
+            max_angle: degrees_to_radius(max_angle),
+        }
+    }
+
+    /// `theta` is the angle in radians between the emitted direction and the axis.
+    pub fn value(&self, theta: f64) -> f64 {
+        if theta > self.max_angle {
+            return 0.;
+        }
+        let n = self.intensities.len();
+        if n == 1 {
+            return self.intensities[0];
+        }
+        let x = theta / self.max_angle * (n - 1) as f64;
+        let i = (x.floor() as usize).min(n - 2);
+        let t = x - i as f64;
+        (1. - t) * self.intensities[i] + t * self.intensities[i + 1]
+    }
+}
+
+#[test]
+fn test_profile_interp() {
+    let profile = EmissionProfile::new(vec![4., 2., 0.], 90.);
+    assert_eq!(profile.value(0.), 1.);
+    assert_eq!(profile.value(degrees_to_radius(45.)), 0.5);
+    assert_eq!(profile.value(degrees_to_radius(67.5)), 0.25);
+    assert_eq!(profile.value(degrees_to_radius(100.)), 0.);
+}
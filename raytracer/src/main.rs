@@ -1,33 +1,74 @@
+// the tree predates the associated float constants
+#![allow(clippy::legacy_numeric_constants)]
+
+mod animation;
 mod camera;
 mod color;
 mod hittable;
+mod light;
 mod material;
 mod pdf;
 mod ray;
+mod render;
 mod rtweekend;
+mod sampler;
 mod vec3;
 
-use color::write_color;
+use animation::{Animation, CameraPath, Track};
 use hittable::{
     aarect::{XyRect, XzRect, YzRect},
+    animated::Animated,
     bvh,
+    cone::Cone,
     constant_medium::ConstantMedium,
-    hittable_list, moving_sphere, FlipFace, HitRecord, Hittable, RotateY, Translate,
+    csg::{Csg, CsgOp},
+    curve::Curve,
+    cylinder::Cylinder,
+    disk::Disk,
+    hittable_list,
+    instance::{Instance, Transform},
+    mesh::Mesh,
+    moving_sphere,
+    ply::Ply,
+    quad::Quad,
+    sdf::{self, Sdf},
+    tlas::Tlas,
+    torus::Torus,
+    triangle::Triangle,
+    FlipFace, Hittable, ObjectId, RotateY, Translate,
 };
-use image::{ImageBuffer, RgbImage};
-use indicatif::{MultiProgress, ProgressBar};
+use light::{profile::EmissionProfile, DirectionalLight, Light, LightList, PointLight, SpotLight};
 use material::{
-    texture::{CheckerTexture, ImageTexture, NoiseTexture},
-    DiffuseLight, ScatterRecord,
+    hair::Hair,
+    texture::{CheckerTexture, ImageTexture, NoiseTexture, SolidColor},
+    DiffuseLight, Material, MaterialId,
 };
 use moving_sphere::MovingSphere;
-use pdf::{HittablePdf, MixturePdf, Pdf};
-use rtweekend::random_double;
-use std::{
-    fs::File,
-    sync::{mpsc, Arc},
-    thread,
+use render::{
+    adaptive::AdaptiveSampling,
+    aov::Aov,
+    denoise::Denoiser,
+    filter::{BlackmanHarrisFilter, BoxFilter, Filter, GaussianFilter, MitchellFilter, TentFilter},
+    integrator::{
+        ambient_occlusion::AmbientOcclusionIntegrator,
+        bdpt::BdptIntegrator,
+        debug::{DebugIntegrator, DebugView},
+        direct::DirectLightingIntegrator,
+        path::{BounceLimits, PathIntegrator},
+        photon::{PhotonMapIntegrator, ProgressivePhotonMapping},
+        Integrator,
+    },
+    metropolis::Metropolis,
+    render, save_aovs, save_image, save_pixels,
+    scene::Scene,
+    RenderSettings, THREAD_NUM,
+};
+use rtweekend::{degrees_to_radius, random_double};
+use sampler::{
+    halton::HaltonSampler, sobol::SobolSampler, stratified::StratifiedSampler, IndependentSampler,
+    Sampler,
 };
+use std::{env, sync::Arc};
 pub use vec3::Vec3;
 pub type Point3 = Vec3;
 pub type Color3 = Vec3;
@@ -35,10 +76,17 @@ use bvh::BVH;
 use hittable::boxes::Boxes;
 use hittable_list::HittableList;
 use ray::Ray;
-use std::f64::INFINITY;
 
 use crate::{
-    camera::Camera,
+    camera::{
+        equirectangular::EquirectangularCamera,
+        fisheye::FisheyeCamera,
+        lens::{ApertureShape, Lens},
+        orthographic::OrthographicCamera,
+        perspective::PerspectiveCamera,
+        shutter::ShutterCurve,
+        Camera,
+    },
     hittable::sphere::Sphere,
     material::{Dielectric, Lambertian, Metal},
     rtweekend::random_double_unit,
@@ -50,58 +98,6 @@ fn is_ci() -> bool {
     option_env!("CI").unwrap_or_default() == "true"
 }
 
-fn ray_color(
-    r: Ray,
-    world: &Arc<dyn Hittable + Send + Sync>,
-    lights: &Arc<HittableList>,
-    background: Color3,
-    depth: isize,
-) -> Color3 {
-    let mut rec = HitRecord::new();
-
-    if depth <= 0 {
-        return Color3::new(0., 0., 0.);
-    }
-
-    if !world.hit(&r, 0.000001, INFINITY, &mut rec) {
-        return background;
-    }
-
-    let mut srec = ScatterRecord::new();
-    let emitted = rec
-        .mat_ptr
-        .clone()
-        .unwrap()
-        .emitted(&r, &rec, rec.u, rec.v, &rec.p);
-
-    if rec.mat_ptr.clone().unwrap().scatter(&r, &rec, &mut srec) {
-        if srec.is_specular {
-            return Vec3::elemul(
-                srec.attenuation,
-                ray_color(srec.specular_ray, world, lights, background, depth - 1),
-            );
-        }
-        let light_ptr = Arc::new(HittablePdf::new(lights.clone(), &rec.p));
-        let mixture_pdf = MixturePdf::new(light_ptr, srec.pdf_ptr.unwrap());
-
-        let scattered = Ray::new(rec.p, mixture_pdf.generate(), r.time());
-        let pdf_val = mixture_pdf.value(&scattered.direction());
-
-        emitted
-            + Vec3::elemul(
-                srec.attenuation,
-                ray_color(scattered, world, lights, background, depth - 1),
-            ) * rec
-                .mat_ptr
-                .clone()
-                .unwrap()
-                .scattering_pdf(&r, &rec, &scattered)
-                / pdf_val
-    } else {
-        emitted
-    }
-}
-
 fn random_scene() -> HittableList {
     let mut world = HittableList::new();
 
@@ -116,6 +112,14 @@ fn random_scene() -> HittableList {
         ground_material,
     )));
 
+    // the small metal and glass spheres are all placements of one sphere
+    let unit_sphere = Arc::new(Sphere::new(
+        Point3::zero(),
+        1.,
+        Arc::new(Lambertian::new(Color3::zero())),
+    ));
+    let glass = Arc::new(Dielectric::new(1.5));
+
     for a in -11..11 {
         for b in -11..11 {
             let choose_mat = random_double_unit();
@@ -144,11 +148,19 @@ fn random_scene() -> HittableList {
                     let albedo = Color3::random(0.5, 1.);
                     let fuzz = random_double(0., 0.5);
                     let sphere_material = Arc::new(Metal::new(albedo, fuzz));
-                    world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
+                    let placement =
+                        Transform::scale(Vec3::ones() * 0.2).then(Transform::translate(center));
+                    world.add(Arc::new(
+                        Instance::new(unit_sphere.clone(), placement)
+                            .with_material(sphere_material),
+                    ));
                 } else {
                     // glass
-                    let sphere_material = Arc::new(Dielectric::new(1.5));
-                    world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
+                    let placement =
+                        Transform::scale(Vec3::ones() * 0.2).then(Transform::translate(center));
+                    world.add(Arc::new(
+                        Instance::new(unit_sphere.clone(), placement).with_material(glass.clone()),
+                    ));
                 }
             }
         }
@@ -261,35 +273,56 @@ fn simple_light() -> HittableList {
     objects
 }
 
+/// The Cornell box's wall and light materials, tagged for the material ID pass.
+#[allow(clippy::type_complexity)]
+fn cornell_materials() -> (
+    Arc<dyn Material + Send + Sync>,
+    Arc<dyn Material + Send + Sync>,
+    Arc<dyn Material + Send + Sync>,
+    Arc<dyn Material + Send + Sync>,
+) {
+    let red = Lambertian::new(Color3::new(0.65, 0.05, 0.05));
+    let white = Lambertian::new(Color3::new(0.73, 0.73, 0.73));
+    let green = Lambertian::new(Color3::new(0.12, 0.45, 0.15));
+    let light = DiffuseLight::new_color(Color3::new(15., 15., 15.));
+    (
+        Arc::new(MaterialId::new(Arc::new(red), 1)),
+        Arc::new(MaterialId::new(Arc::new(white), 2)),
+        Arc::new(MaterialId::new(Arc::new(green), 3)),
+        Arc::new(MaterialId::new(Arc::new(light), 4)),
+    )
+}
+
 fn cornell_box() -> HittableList {
     let mut objects = HittableList::new();
 
-    let red = Arc::new(Lambertian::new(Color3::new(0.65, 0.05, 0.05)));
-    let white = Arc::new(Lambertian::new(Color3::new(0.73, 0.73, 0.73)));
-    let green = Arc::new(Lambertian::new(Color3::new(0.12, 0.45, 0.15)));
-    let light = Arc::new(DiffuseLight::new_color(Color3::new(15., 15., 15.)));
+    let (red, white, green, light) = cornell_materials();
 
-    objects.add(Arc::new(YzRect::new(0., 555., 0., 555., 555., green)));
-    objects.add(Arc::new(YzRect::new(0., 555., 0., 555., 0., red)));
-    objects.add(Arc::new(FlipFace::new(Arc::new(XzRect::new(
-        213., 343., 227., 332., 554., light,
-    )))));
-    objects.add(Arc::new(XzRect::new(0., 555., 0., 555., 0., white.clone())));
-    objects.add(Arc::new(XzRect::new(
-        0.,
-        555.,
-        0.,
-        555.,
-        555.,
-        white.clone(),
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(YzRect::new(0., 555., 0., 555., 555., green)),
+        1,
     )));
-    objects.add(Arc::new(XyRect::new(
-        0.,
-        555.,
-        0.,
-        555.,
-        555.,
-        white.clone(),
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(YzRect::new(0., 555., 0., 555., 0., red)),
+        2,
+    )));
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(FlipFace::new(Arc::new(XzRect::new(
+            213., 343., 227., 332., 554., light,
+        )))),
+        3,
+    )));
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(XzRect::new(0., 555., 0., 555., 0., white.clone())),
+        4,
+    )));
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(XzRect::new(0., 555., 0., 555., 555., white.clone())),
+        5,
+    )));
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(XyRect::new(0., 555., 0., 555., 555., white.clone())),
+        6,
     )));
 
     // let aluminum = Arc::new(Metal::new(Color3::new(0.8, 0.85, 0.88), 0.0));
@@ -300,13 +333,12 @@ fn cornell_box() -> HittableList {
     ));
     box1 = Arc::new(RotateY::new(box1, 15.));
     box1 = Arc::new(Translate::new(box1, Vec3::new(265., 0., 295.)));
-    objects.add(box1);
+    objects.add(Arc::new(ObjectId::new(box1, 7)));
 
-    let glass = Arc::new(Dielectric::new(1.5));
-    objects.add(Arc::new(Sphere::new(
-        Point3::new(190., 90., 190.),
-        90.,
-        glass,
+    let glass = Arc::new(MaterialId::new(Arc::new(Dielectric::new(1.5)), 5));
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(Sphere::new(Point3::new(190., 90., 190.), 90., glass)),
+        8,
     )));
 
     objects
@@ -315,30 +347,31 @@ fn cornell_box() -> HittableList {
 fn cornell_smoke() -> HittableList {
     let mut objects = HittableList::new();
 
-    let red = Arc::new(Lambertian::new(Color3::new(0.65, 0.05, 0.05)));
-    let white = Arc::new(Lambertian::new(Color3::new(0.73, 0.73, 0.73)));
-    let green = Arc::new(Lambertian::new(Color3::new(0.12, 0.45, 0.15)));
-    let light = Arc::new(DiffuseLight::new_color(Color3::new(15., 15., 15.)));
+    let (red, white, green, light) = cornell_materials();
 
-    objects.add(Arc::new(YzRect::new(0., 555., 0., 555., 555., green)));
-    objects.add(Arc::new(YzRect::new(0., 555., 0., 555., 0., red)));
-    objects.add(Arc::new(XzRect::new(213., 343., 227., 332., 554., light)));
-    objects.add(Arc::new(XzRect::new(0., 555., 0., 555., 0., white.clone())));
-    objects.add(Arc::new(XzRect::new(
-        0.,
-        555.,
-        0.,
-        555.,
-        555.,
-        white.clone(),
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(YzRect::new(0., 555., 0., 555., 555., green)),
+        3,
     )));
-    objects.add(Arc::new(XyRect::new(
-        0.,
-        555.,
-        0.,
-        555.,
-        555.,
-        white.clone(),
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(YzRect::new(0., 555., 0., 555., 0., red)),
+        4,
+    )));
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(XzRect::new(213., 343., 227., 332., 554., light)),
+        5,
+    )));
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(XzRect::new(0., 555., 0., 555., 0., white.clone())),
+        6,
+    )));
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(XzRect::new(0., 555., 0., 555., 555., white.clone())),
+        7,
+    )));
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(XyRect::new(0., 555., 0., 555., 555., white.clone())),
+        8,
     )));
 
     let mut box1: Arc<dyn Hittable + Send + Sync> = Arc::new(Boxes::new(
@@ -357,15 +390,328 @@ fn cornell_smoke() -> HittableList {
     box2 = Arc::new(RotateY::new(box2, -18.));
     box2 = Arc::new(Translate::new(box2, Vec3::new(130., 0., 65.)));
 
-    objects.add(Arc::new(ConstantMedium::new_color(
-        box1,
-        0.01,
-        Color3::zero(),
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(ConstantMedium::new_color(box1, 0.01, Color3::zero())),
+        1,
     )));
-    objects.add(Arc::new(ConstantMedium::new_color(
-        box2,
-        0.01,
-        Color3::ones(),
+    objects.add(Arc::new(ObjectId::new(
+        Arc::new(ConstantMedium::new_color(box2, 0.01, Color3::ones())),
+        2,
+    )));
+
+    objects
+}
+
+fn light_types() -> HittableList {
+    let mut objects = HittableList::new();
+
+    let pertext = Arc::new(NoiseTexture::new(4.));
+    objects.add(Arc::new(Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::new_texture(pertext)),
+    )));
+    objects.add(Arc::new(Sphere::new(
+        Point3::new(0., 2., 0.),
+        2.,
+        Arc::new(Lambertian::new(Color3::new(0.8, 0.8, 0.8))),
+    )));
+    objects.add(Arc::new(Sphere::new(
+        Point3::new(-2., 1., 4.),
+        1.,
+        Arc::new(Metal::new(Color3::new(0.8, 0.6, 0.2), 0.1)),
+    )));
+
+    // a two-sided panel hanging above the scene, dimmer towards grazing angles
+    let profile = Arc::new(EmissionProfile::new(vec![1., 0.9, 0.6, 0.3, 0.1], 90.));
+    let panel = Arc::new(DiffuseLight::new_full(
+        Arc::new(SolidColor::new(Color3::ones() * 4.)),
+        true,
+        Some(profile),
+    ));
+    objects.add(Arc::new(XzRect::new(-1., 1., -1., 1., 7., panel)));
+
+    objects
+}
+
+/// The ceiling light of the showcase scenes. Its shape with a material that
+/// doesn't matter stands in for it in `lights`.
+fn showcase_light(mat_ptr: Arc<dyn Material + Send + Sync>) -> Disk {
+    Disk::new(Point3::new(0., 7., 2.), Vec3::new(0., -1., 0.), 2., mat_ptr)
+}
+
+/// Puts `objects` on the checkered ground of the showcase scenes, under
+/// their light.
+fn showcase(mut objects: HittableList) -> HittableList {
+    let checker = Arc::new(CheckerTexture::new_solid(
+        Color3::new(0.2, 0.3, 0.1),
+        Color3::new(0.9, 0.9, 0.9),
+    ));
+    objects.add(Arc::new(Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::new_texture(checker)),
+    )));
+    objects.add(Arc::new(showcase_light(Arc::new(DiffuseLight::new_color(
+        Color3::ones() * 6.,
+    )))));
+    objects
+}
+
+fn primitives() -> HittableList {
+    let mut objects = HittableList::new();
+
+    let up = Vec3::new(0., 1., 0.);
+    objects.add(Arc::new(Cylinder::new(
+        Point3::new(-4., 0., 0.),
+        up,
+        1.,
+        2.5,
+        Arc::new(Lambertian::new(Color3::new(0.8, 0.3, 0.2))),
+    )));
+    objects.add(Arc::new(Cone::new(
+        Point3::new(-1.5, 0., 1.5),
+        up,
+        1.,
+        2.5,
+        Arc::new(Metal::new(Color3::new(0.8, 0.6, 0.2), 0.2)),
+    )));
+    objects.add(Arc::new(Torus::new(
+        Point3::new(1.5, 1.5, 0.),
+        Vec3::new(1., 1., 0.5),
+        1.,
+        0.35,
+        Arc::new(Lambertian::new(Color3::new(0.2, 0.4, 0.8))),
+    )));
+    objects.add(Arc::new(Triangle::new(
+        Point3::new(3.5, 0., 2.),
+        Point3::new(5.5, 0., 1.),
+        Point3::new(4.5, 2.5, 1.5),
+        Arc::new(Lambertian::new(Color3::new(0.3, 0.7, 0.3))),
+    )));
+    objects.add(Arc::new(Quad::new(
+        Point3::new(-3., 0., -3.),
+        Vec3::new(6., 0., -1.),
+        Vec3::new(0., 4., 0.),
+        Arc::new(Metal::new(Color3::new(0.8, 0.85, 0.9), 0.)),
+    )));
+
+    objects
+}
+
+fn csg() -> HittableList {
+    let mut objects = HittableList::new();
+
+    // a biconvex lens blank
+    let glass = Arc::new(Dielectric::new(1.5));
+    objects.add(Arc::new(Csg::new(
+        CsgOp::Intersection,
+        Arc::new(Sphere::new(Point3::new(-2.5, 1.5, -2.), 2., glass.clone())),
+        Arc::new(Sphere::new(Point3::new(-2.5, 1.5, 1.), 2., glass)),
+    )));
+
+    // a sphere drilled through along two axes
+    let red = Arc::new(Lambertian::new(Color3::new(0.8, 0.3, 0.2)));
+    let drill = Arc::new(Csg::new(
+        CsgOp::Union,
+        Arc::new(Cylinder::new(
+            Point3::new(2., 1.5, -2.),
+            Vec3::new(0., 0., 1.),
+            0.6,
+            4.,
+            red.clone(),
+        )),
+        Arc::new(Cylinder::new(
+            Point3::new(0., 1.5, 0.),
+            Vec3::new(1., 0., 0.),
+            0.6,
+            4.,
+            red.clone(),
+        )),
+    ));
+    objects.add(Arc::new(Csg::new(
+        CsgOp::Difference,
+        Arc::new(Sphere::new(Point3::new(2., 1.5, 0.), 1.5, red)),
+        drill,
+    )));
+
+    objects
+}
+
+fn sdf_shapes() -> HittableList {
+    let mut objects = HittableList::new();
+
+    objects.add(Arc::new(Sdf::new(
+        sdf::rounded_box(Point3::new(-4., 1., 0.), Vec3::new(0.8, 0.8, 0.8), 0.2),
+        Point3::new(-5.1, -0.1, -1.1),
+        Point3::new(-2.9, 2.1, 1.1),
+        Arc::new(Lambertian::new(Color3::new(0.8, 0.3, 0.2))),
+    )));
+    objects.add(Arc::new(Sdf::new(
+        sdf::smooth_union(
+            sdf::sphere(Point3::new(-0.6, 1., 0.), 0.9),
+            sdf::sphere(Point3::new(0.6, 1.5, 0.), 0.7),
+            0.5,
+        ),
+        Point3::new(-1.6, 0., -1.),
+        Point3::new(1.4, 2.3, 1.),
+        Arc::new(Metal::new(Color3::new(0.8, 0.6, 0.2), 0.1)),
+    )));
+    objects.add(Arc::new(
+        Sdf::new(
+            sdf::mandelbulb(Point3::new(3.5, 1.3, 0.), 1.2, 8., 10),
+            Point3::new(2.1, -0.1, -1.4),
+            Point3::new(4.9, 2.7, 1.4),
+            Arc::new(Lambertian::new(Color3::new(0.2, 0.4, 0.8))),
+        )
+        .with_max_steps(512),
+    ));
+
+    objects
+}
+
+fn subdivision() -> HittableList {
+    let mut objects = HittableList::new();
+
+    // a cube smoothed into a rounded blob
+    let cube = Mesh::new(
+        vec![
+            Point3::new(-3.5, 0., -1.),
+            Point3::new(-1.5, 0., -1.),
+            Point3::new(-1.5, 2., -1.),
+            Point3::new(-3.5, 2., -1.),
+            Point3::new(-3.5, 0., 1.),
+            Point3::new(-1.5, 0., 1.),
+            Point3::new(-1.5, 2., 1.),
+            Point3::new(-3.5, 2., 1.),
+        ],
+        vec![
+            vec![0, 3, 2, 1],
+            vec![4, 5, 6, 7],
+            vec![0, 1, 5, 4],
+            vec![2, 3, 7, 6],
+            vec![0, 4, 7, 3],
+            vec![1, 2, 6, 5],
+        ],
+    );
+    let smooth = cube
+        .subdivide_catmull_clark()
+        .subdivide_catmull_clark()
+        .subdivide_catmull_clark();
+    objects.add(Arc::new(BVH::new(
+        &smooth.triangulate(Arc::new(Metal::new(Color3::new(0.8, 0.6, 0.2), 0.1))),
+        0.,
+        0.,
+    )));
+
+    // an octahedron rounded into a ball, then carved by marble noise
+    let octahedron = Mesh::new(
+        vec![
+            Point3::new(2.5, 2.5, 0.),
+            Point3::new(2.5, -0.1, 0.),
+            Point3::new(3.8, 1.2, 0.),
+            Point3::new(1.2, 1.2, 0.),
+            Point3::new(2.5, 1.2, 1.3),
+            Point3::new(2.5, 1.2, -1.3),
+        ],
+        vec![
+            vec![0, 4, 2],
+            vec![0, 2, 5],
+            vec![0, 5, 3],
+            vec![0, 3, 4],
+            vec![1, 2, 4],
+            vec![1, 5, 2],
+            vec![1, 3, 5],
+            vec![1, 4, 3],
+        ],
+    );
+    let mut ball = octahedron;
+    for _ in 0..5 {
+        ball = ball.subdivide_loop();
+    }
+    let rock = ball.displace(&NoiseTexture::new(4.), 0.15);
+    objects.add(Arc::new(BVH::new(
+        &rock.triangulate(Arc::new(Lambertian::new(Color3::new(0.2, 0.4, 0.8)))),
+        0.,
+        0.,
+    )));
+
+    objects
+}
+
+fn hair() -> HittableList {
+    let mut objects = HittableList::new();
+
+    // a ball of auburn fur, each strand bending down a little at its tip
+    let center = Point3::new(-2., 1.2, 0.);
+    let fur: Arc<dyn Material + Send + Sync> = Arc::new(Hair::from_melanin(1.3, 0.8, 0.3, 0.3));
+    objects.add(Arc::new(Sphere::new(
+        center,
+        0.8,
+        Arc::new(Lambertian::new(Color3::new(0.15, 0.07, 0.03))),
+    )));
+    let mut strands = HittableList::new();
+    for _ in 0..4000 {
+        let out = Vec3::random_unit_vector();
+        let root = center + 0.78 * out;
+        let length = random_double(0.3, 0.45);
+        let droop = Vec3::new(0., -0.15, 0.) + 0.05 * Vec3::random_in_unit_sphere();
+        strands.add(Arc::new(Curve::new(
+            [
+                root,
+                root + length / 3. * out,
+                root + 2. * length / 3. * out + droop / 3.,
+                root + length * out + droop,
+            ],
+            0.012,
+            0.002,
+            fur.clone(),
+        )));
+    }
+    objects.add(Arc::new(BVH::new(&strands, 0., 0.)));
+
+    // a tuft of grass blades leaning every which way
+    let grass: Arc<dyn Material + Send + Sync> =
+        Arc::new(Lambertian::new(Color3::new(0.2, 0.5, 0.1)));
+    let mut blades = HittableList::new();
+    for _ in 0..1500 {
+        let root = Point3::new(random_double(1., 4.), 0., random_double(-1.5, 1.5));
+        let height = random_double(1., 2.);
+        let lean = Vec3::new(random_double(-0.6, 0.6), 0., random_double(-0.6, 0.6));
+        blades.add(Arc::new(Curve::new(
+            [
+                root,
+                root + Vec3::new(0., height / 2., 0.),
+                root + Vec3::new(0., height, 0.) + lean / 2.,
+                root + Vec3::new(0., height, 0.) + lean * 1.5,
+            ],
+            0.06,
+            0.005,
+            grass.clone(),
+        )));
+    }
+    objects.add(Arc::new(BVH::new(&blades, 0., 0.)));
+
+    objects
+}
+
+fn scans() -> HittableList {
+    let mut objects = HittableList::new();
+
+    // an ASCII mesh shaded with its vertex colors
+    let sphere = Ply::load("raytracer/res/icosphere.ply").unwrap();
+    objects.add(Arc::new(Instance::new(
+        Arc::new(sphere.triangles(None)),
+        Transform::scale(Vec3::ones() * 1.2).then(Transform::translate(Vec3::new(-2., 1.2, 0.))),
+    )));
+
+    // a binary point cloud drawn as small spheres
+    let torus = Ply::load("raytracer/res/torus_points.ply").unwrap();
+    objects.add(Arc::new(Instance::new(
+        Arc::new(torus.splats(0.05, None)),
+        Transform::rotate(Vec3::new(1., 0., 0.), 60.)
+            .then(Transform::scale(Vec3::ones() * 1.3))
+            .then(Transform::translate(Vec3::new(2.5, 1.5, 0.))),
     )));
 
     objects
@@ -374,6 +720,8 @@ fn cornell_smoke() -> HittableList {
 fn final_scene() -> HittableList {
     let mut boxes1 = HittableList::new();
     let ground = Arc::new(Lambertian::new(Color3::new(0.48, 0.83, 0.53)));
+    // every box of the ground is a placement of this one
+    let unit_box = Arc::new(Boxes::new(&Point3::zero(), &Point3::ones(), ground));
 
     let boxes_per_side = 20;
     for i in 0..boxes_per_side {
@@ -382,15 +730,11 @@ fn final_scene() -> HittableList {
             let x0 = -1000. + i as f64 * w;
             let z0 = -1000. + j as f64 * w;
             let y0 = 0.;
-            let x1 = x0 + w;
             let y1 = random_double(1., 101.);
-            let z1 = z0 + w;
 
-            boxes1.add(Arc::new(Boxes::new(
-                &Point3::new(x0, y0, z0),
-                &Point3::new(x1, y1, z1),
-                ground.clone(),
-            )));
+            let placement = Transform::scale(Vec3::new(w, y1 - y0, w))
+                .then(Transform::translate(Point3::new(x0, y0, z0)));
+            boxes1.add(Arc::new(Instance::new(unit_box.clone(), placement)));
         }
     }
 
@@ -475,17 +819,61 @@ fn final_scene() -> HittableList {
         )));
     }
 
-    objects.add(Arc::new(Translate::new(
-        Arc::new(RotateY::new(Arc::new(BVH::new(&boxes2, 0.0, 1.0)), 15.)),
-        Vec3::new(-100., 270., 395.),
+    let placement = Transform::rotate(Vec3::new(0., 1., 0.), 15.)
+        .then(Transform::translate(Vec3::new(-100., 270., 395.)));
+    objects.add(Arc::new(Instance::new(
+        Arc::new(BVH::new(&boxes2, 0.0, 1.0)),
+        placement,
     )));
 
     objects
 }
 
-fn main() {
-    const THREAD_NUM: usize = 4;
+fn animated_scene() -> HittableList {
+    let mut objects = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::new_solid(
+        Color3::new(0.2, 0.3, 0.1),
+        Color3::new(0.9, 0.9, 0.9),
+    ));
+    objects.add(Arc::new(Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::new_texture(checker)),
+    )));
+
+    // a box sliding across the ground while turning around
+    let crate_box = Arc::new(Boxes::new(
+        &Point3::new(-0.5, 0., -0.5),
+        &Point3::new(0.5, 1., 0.5),
+        Arc::new(Lambertian::new(Color3::new(0.7, 0.3, 0.1))),
+    ));
+    objects.add(Arc::new(Animated::new(
+        crate_box,
+        Track::new(0., Vec3::new(-3., 0., 2.)).key(2., Vec3::new(3., 0., 2.)),
+        Track::new(0., 0.).key(2., 180.),
+    )));
 
+    // a bouncing ball
+    let ball = Arc::new(Sphere::new(
+        Point3::zero(),
+        1.,
+        Arc::new(Metal::new(Color3::new(0.7, 0.6, 0.5), 0.0)),
+    ));
+    objects.add(Arc::new(Animated::new(
+        ball,
+        Track::new(0., Vec3::new(0., 1., 0.))
+            .key(0.5, Vec3::new(0., 3., 0.))
+            .key(1., Vec3::new(0., 1., 0.))
+            .key(1.5, Vec3::new(0., 3., 0.))
+            .key(2., Vec3::new(0., 1., 0.)),
+        Track::constant(0.),
+    )));
+
+    objects
+}
+
+fn main() {
     // get environment variable CI, which is true for GitHub Actions
     let is_ci = is_ci();
 
@@ -497,50 +885,70 @@ fn main() {
     let path = "output/test.jpg";
     let quality = 60; // From 0 to 100, suggested value: 60
     let mut samples_per_pixel: usize = 100;
-    let max_depth = 50;
+    let max_depth: usize = 50;
 
     // World
-    let world: BVH;
+    let world: Arc<dyn Hittable + Send + Sync>;
+    let mut tlas: Option<Tlas> = None;
 
     let mut lookfrom = Point3::ones();
     let mut lookat = Point3::zero();
     let mut vfov = 40.;
     let mut aperture = 0.;
+    let mut aperture_shape = ApertureShape::Circle;
+    let mut lens: Option<Lens> = None;
+    let mut shutter_curve = ShutterCurve::Box;
+    let readout = 0.;
+    let mut animation: Option<Animation> = None;
+    let mut camera_path: Option<CameraPath> = None;
+    let mut adaptive: Option<AdaptiveSampling> = None;
+    let mut denoiser: Option<Denoiser> = None;
+    let mut aovs: Vec<Aov> = Vec::new();
+    let mut clamp: Option<f64> = None;
+    let mut regularization: Option<f64> = None;
+    let mut russian_roulette: Option<usize> = Some(5);
+    let mut bounce_limits = BounceLimits::new(max_depth, max_depth, max_depth, max_depth);
+    let photon_count = 500_000; // per pass when progressive
+    let photon_passes = 16;
+    let mut photon_radius = 1.; // at the start when progressive
+    let mut ao_radius = 1.;
     let mut background = Color3::zero();
     let mut lights = HittableList::new();
+    let mut delta_lights: Vec<Arc<dyn Light + Send + Sync>> = Vec::new();
 
     match 6 {
         1 => {
-            world = BVH::new(&random_scene(), 0., 1.);
+            world = Arc::new(BVH::new(&random_scene(), 0., 1.));
             lookfrom = Point3::new(13., 2., 3.);
             lookat = Point3::new(0., 0., 0.);
-            vfov = 20.0;
-            aperture = 0.1;
+            lens = Some(Lens::new(57., 5.6, 36., 24., 100.));
+            aperture_shape = ApertureShape::polygon(6, 0.);
+            shutter_curve = ShutterCurve::Triangle;
             background = Color3::new(0.7, 0.8, 1.);
         }
         2 => {
-            world = BVH::new(&two_spheres(), 0., 0.);
+            world = Arc::new(BVH::new(&two_spheres(), 0., 0.));
             lookfrom = Point3::new(13., 2., 3.);
             lookat = Point3::new(0., 0., 0.);
             vfov = 20.0;
             background = Color3::new(0.7, 0.8, 1.);
         }
         3 => {
-            world = BVH::new(&two_perlin_spheres(), 0., 0.);
+            world = Arc::new(BVH::new(&two_perlin_spheres(), 0., 0.));
             lookfrom = Point3::new(13., 2., 3.);
             lookat = Point3::new(0., 0., 0.);
             vfov = 20.0;
             background = Color3::new(0.7, 0.8, 1.);
         }
         4 => {
-            world = BVH::new(&earth(), 0., 0.);
+            world = Arc::new(BVH::new(&earth(), 0., 0.));
             lookfrom = Point3::new(13., 2., 3.);
             lookat = Point3::new(0., 0., 0.);
             vfov = 20.0;
             background = Color3::new(0.7, 0.8, 1.);
         }
         5 => {
-            world = BVH::new(&simple_light(), 0., 0.);
+            world = Arc::new(BVH::new(&simple_light(), 0., 0.));
             samples_per_pixel = 400;
             background = Color3::zero();
             lookfrom = Point3::new(26., 3., 6.);
@@ -548,10 +956,15 @@ fn main() {
             vfov = 20.0
         }
         6 => {
-            world = BVH::new(&cornell_box(), 0., 0.);
+            world = Arc::new(BVH::new(&cornell_box(), 0., 0.));
             aspect_ratio = 1.;
             width = 600;
             samples_per_pixel = 1000;
+            // the glass sphere's caustic is a firefly magnet
+            clamp = Some(10.);
+            regularization = Some(0.1);
+            photon_radius = 15.;
+            ao_radius = 100.;
             background = Color3::zero();
             lookfrom = Point3::new(278., 278., -800.);
             lookat = Point3::new(278., 278., 0.);
@@ -571,19 +984,28 @@ fn main() {
             )));
         }
         7 => {
-            world = BVH::new(&cornell_smoke(), 0., 0.);
+            world = Arc::new(BVH::new(&cornell_smoke(), 0., 0.));
+            // deep inside the smoke paths contribute next to nothing
+            bounce_limits.volume = 16;
+            ao_radius = 100.;
             aspect_ratio = 1.;
             width = 600;
             samples_per_pixel = 200;
+            denoiser = Some(Denoiser::new(5, 4., 0.3, 0.1, 0.1));
+            aovs = Aov::ALL.to_vec();
             lookfrom = Point3::new(278., 278., -800.);
             lookat = Point3::new(278., 278., 0.);
             vfov = 40.;
         }
         8 => {
-            world = BVH::new(&final_scene(), 0., 1.);
+            world = Arc::new(BVH::new(&final_scene(), 0., 1.));
             aspect_ratio = 1.;
             width = 800;
             samples_per_pixel = 10000;
+            adaptive = Some(AdaptiveSampling::new(64, 0.005));
+            // the foggy sphere needs long paths through the medium
+            russian_roulette = Some(8);
+            ao_radius = 100.;
             background = Color3::zero();
             lookfrom = Point3::new(478., 278., -600.);
             lookat = Point3::new(278., 278., 0.);
@@ -597,97 +1019,313 @@ fn main() {
                 Arc::new(Lambertian::new(Color3::zero())),
             )));
         }
+        9 => {
+            world = Arc::new(BVH::new(&light_types(), 0., 0.));
+            samples_per_pixel = 200;
+            background = Color3::zero();
+            lookfrom = Point3::new(26., 3., 6.);
+            lookat = Point3::new(0., 2., 0.);
+            vfov = 20.;
+            lights.add(Arc::new(XzRect::new(
+                -1.,
+                1.,
+                -1.,
+                1.,
+                7.,
+                Arc::new(Lambertian::new(Color3::zero())),
+            )));
+            delta_lights.push(Arc::new(SpotLight::new(
+                Point3::new(6., 8., 6.),
+                Point3::new(0., 0., 0.),
+                Color3::new(1., 0.9, 0.7) * 200.,
+                20.,
+                15.,
+            )));
+            delta_lights.push(Arc::new(PointLight::new_profile(
+                Point3::new(-4., 3., 6.),
+                Color3::new(0.3, 0.4, 1.) * 10.,
+                Vec3::new(0., -1., 0.),
+                Arc::new(EmissionProfile::new(vec![1., 1., 0.8, 0.4, 0.1, 0.], 120.)),
+            )));
+            delta_lights.push(Arc::new(DirectionalLight::new(
+                Vec3::new(-1., -1., -0.5),
+                Color3::new(0.15, 0.15, 0.1),
+            )));
+        }
+        10 => {
+            let sequence = Animation::new(48, 24., 180.);
+            // refit to each frame's shutter interval as the objects move
+            let (time0, time1) = sequence.shutter_interval(0);
+            let top = Tlas::new(&animated_scene(), time0, time1);
+            world = Arc::new(top.clone());
+            tlas = Some(top);
+            background = Color3::new(0.7, 0.8, 1.);
+            lookfrom = Point3::new(13., 2., 3.);
+            lookat = Point3::new(0., 1., 0.);
+            vfov = 30.;
+            camera_path = Some(CameraPath::new(
+                Track::new(0., lookfrom)
+                    .key(1., Point3::new(3., 3., 13.))
+                    .key(2., Point3::new(-13., 4., 3.)),
+                Track::new(0., lookat).key(2., Point3::new(0., 0.5, 0.)),
+            ));
+            animation = Some(sequence);
+        }
+        showcase_scene @ 11..=16 => {
+            let objects = match showcase_scene {
+                11 => primitives(),
+                12 => csg(),
+                13 => sdf_shapes(),
+                14 => subdivision(),
+                15 => hair(),
+                _ => scans(),
+            };
+            world = Arc::new(BVH::new(&showcase(objects), 0., 0.));
+            samples_per_pixel = 400;
+            background = Color3::new(0.05, 0.05, 0.08);
+            lookfrom = Point3::new(3., 5., 16.);
+            lookat = Point3::new(0., 1., 0.);
+            vfov = 30.;
+            lights.add(Arc::new(showcase_light(Arc::new(Lambertian::new(
+                Color3::zero(),
+            )))));
+        }
         _ => {
-            world = BVH::new(&HittableList::new(), 0., 0.);
+            world = Arc::new(BVH::new(&HittableList::new(), 0., 0.));
         }
     }
-    let height: usize = (width as f64 / aspect_ratio) as usize;
-
-    // Camera
+    // Camera: 0 perspective, 1 orthographic, 2 fisheye, 3 panorama, 4 stereo panorama
+    let projection = 0;
+    match projection {
+        3 => aspect_ratio = 2.,
+        4 => aspect_ratio = 1.,
+        _ => {}
+    }
 
     let vup = Vec3::new(0., 1., 0.);
-    let dist_to_focus = 10.0;
-
-    let cam = Camera::new(
-        lookfrom,
-        lookat,
-        vup,
-        vfov,
-        aspect_ratio,
-        aperture,
-        dist_to_focus,
-        0.,
-        1.,
-    );
-
-    samples_per_pixel = (samples_per_pixel / THREAD_NUM + 1) * THREAD_NUM;
-    let world: Arc<dyn Hittable + Send + Sync> = Arc::new(world);
-    let lights = Arc::new(lights);
+    if let Some(lens) = &lens {
+        vfov = lens.vfov(aspect_ratio);
+        aperture = lens.aperture();
+    }
 
-    // Create image data
-    let mut img: RgbImage = ImageBuffer::new(width.try_into().unwrap(), height.try_into().unwrap());
+    let make_camera = |lookfrom: Point3,
+                       lookat: Point3,
+                       time0: f64,
+                       time1: f64|
+     -> Arc<dyn Camera + Send + Sync> {
+        let dist_to_focus = (lookfrom - lookat).length();
+        match projection {
+            1 => {
+                // same framing at the focus plane as the perspective camera
+                let view_height = 2. * dist_to_focus * (degrees_to_radius(vfov) / 2.).tan();
+                Arc::new(
+                    OrthographicCamera::new(
+                        lookfrom,
+                        lookat,
+                        vup,
+                        view_height,
+                        aspect_ratio,
+                        time0,
+                        time1,
+                    )
+                    .with_shutter(shutter_curve.clone(), readout),
+                )
+            }
+            2 => Arc::new(
+                FisheyeCamera::new(lookfrom, lookat, vup, 180., aspect_ratio, time0, time1)
+                    .with_shutter(shutter_curve.clone(), readout),
+            ),
+            3 => Arc::new(
+                EquirectangularCamera::new(lookfrom, lookat, vup, time0, time1)
+                    .with_shutter(shutter_curve.clone(), readout),
+            ),
+            4 => Arc::new(
+                EquirectangularCamera::new_stereo(
+                    lookfrom,
+                    lookat,
+                    vup,
+                    0.064 * dist_to_focus,
+                    time0,
+                    time1,
+                )
+                .with_shutter(shutter_curve.clone(), readout),
+            ),
+            _ => Arc::new(
+                PerspectiveCamera::new(
+                    lookfrom,
+                    lookat,
+                    vup,
+                    vfov,
+                    aspect_ratio,
+                    aperture,
+                    dist_to_focus,
+                    time0,
+                    time1,
+                )
+                .with_aperture(aperture_shape.clone())
+                .with_shutter(shutter_curve.clone(), readout),
+            ),
+        }
+    };
+    let height: usize = (width as f64 / aspect_ratio) as usize;
 
-    // Progress bar UI powered by library `indicatif`
-    // You can use indicatif::ProgressStyle to make it more beautiful
-    // You can also use indicatif::MultiProgress in multi-threading to show progress of each thread
-    let bar_collection = MultiProgress::new();
+    samples_per_pixel = (samples_per_pixel / THREAD_NUM + 1) * THREAD_NUM;
+    let scene = Arc::new(Scene::new(
+        world,
+        LightList::new(lights, delta_lights),
+        background,
+    ));
 
-    let mut ans = Vec::new();
-    ans.resize(width, Vec::new());
-    for iter in &mut ans {
-        iter.resize(height, Color3::zero());
+    let mut path_integrator = PathIntegrator::new(max_depth).with_bounce_limits(bounce_limits);
+    if let Some(min_bounces) = russian_roulette {
+        path_integrator = path_integrator.with_russian_roulette(min_bounces);
+    }
+    if let Some(radius) = regularization {
+        path_integrator = path_integrator.with_regularization(radius);
     }
 
-    let (tx, rx) = mpsc::channel();
-    for _k in 0..THREAD_NUM {
-        let camm = cam.clone();
-        let world_t = world.clone();
-        let lights_t = lights.clone();
-        let tx_k = tx.clone();
-        let mut ans_t = ans.clone();
-        let bar = bar_collection.add(if is_ci {
-            ProgressBar::hidden()
-        } else {
-            ProgressBar::new((height * width) as u64)
-        });
-        thread::spawn(move || {
-            for (i, iter) in ans_t.iter_mut().enumerate().take(width) {
-                for (j, jter) in iter.iter_mut().enumerate().take(height) {
-                    for _s in 0..samples_per_pixel / THREAD_NUM {
-                        let u = (i as f64 + random_double_unit()) / (width - 1) as f64;
-                        let v = (j as f64 + random_double_unit()) / (height - 1) as f64;
-                        let r = camm.get_ray(u, v);
-                        *jter += ray_color(r, &world_t, &lights_t, background, max_depth);
-                    }
-                    bar.inc(1);
+    // Integrator: 0 path tracing, 1 bidirectional path tracing, 2 photon mapping,
+    // 3 progressive photon mapping (with `samples_per_pixel` per pass), 4 direct
+    // lighting only, 5 ambient occlusion, 6 normals, 7 UVs, 8 BVH traversal heat map.
+    // Pick one without rebuilding with e.g. `INTEGRATOR=5 cargo run --release`
+    let integrator_choice: usize = env::var("INTEGRATOR")
+        .ok()
+        .and_then(|integrator| integrator.parse().ok())
+        .unwrap_or(0);
+    let mut progressive = None;
+    let mut photon_mapping = None;
+    let integrator: Arc<dyn Integrator + Send + Sync> = match integrator_choice {
+        1 => Arc::new(BdptIntegrator::new(max_depth)),
+        2 => {
+            // the map is traced for every frame, in its world and shutter interval
+            photon_mapping = Some((photon_count, photon_radius));
+            Arc::new(path_integrator)
+        }
+        3 => {
+            progressive = Some(ProgressivePhotonMapping::new(
+                photon_count,
+                photon_radius,
+                max_depth,
+            ));
+            Arc::new(path_integrator)
+        }
+        4 => Arc::new(DirectLightingIntegrator::new(max_depth)),
+        5 => Arc::new(AmbientOcclusionIntegrator::new(ao_radius, 16)),
+        6 => Arc::new(DebugIntegrator::new(DebugView::Normal)),
+        7 => Arc::new(DebugIntegrator::new(DebugView::Uv)),
+        8 => Arc::new(DebugIntegrator::new(DebugView::BvhHeatMap {
+            max_nodes: 64,
+        })),
+        _ => Arc::new(path_integrator),
+    };
+
+    // Reconstruction filter: 0 box, 1 tent, 2 Gaussian, 3 Mitchell-Netravali, 4 Blackman-Harris
+    let filter: Arc<dyn Filter + Send + Sync> = match 0 {
+        1 => Arc::new(TentFilter::new(1.)),
+        2 => Arc::new(GaussianFilter::new(1.5, 2.)),
+        3 => Arc::new(MitchellFilter::new(2., 1. / 3., 1. / 3.)),
+        4 => Arc::new(BlackmanHarrisFilter::new(1.5)),
+        _ => Arc::new(BoxFilter::new(0.5)),
+    };
+
+    // Sampler: 0 independent, 1 stratified, 2 Halton, 3 Owen-scrambled Sobol
+    let sampler: Box<dyn Sampler + Send + Sync> = match 0 {
+        1 => Box::new(StratifiedSampler::new(samples_per_pixel)),
+        2 => Box::new(HaltonSampler::new()),
+        3 => Box::new(SobolSampler::new()),
+        _ => Box::new(IndependentSampler),
+    };
+
+    let settings = RenderSettings {
+        width,
+        height,
+        samples_per_pixel,
+        filter,
+        sampler,
+        adaptive,
+        features: denoiser.is_some() || !aovs.is_empty(),
+        clamp,
+        hide_progress: is_ci,
+    };
+
+    // Render mode: 0 independent samples, 1 primary sample space Metropolis light
+    // transport with `samples_per_pixel` mutations per pixel
+    let metropolis = match 0 {
+        1 => Some(Metropolis::new(samples_per_pixel)),
+        _ => None,
+    };
+    let render_film =
+        |cam: &Arc<dyn Camera + Send + Sync>, scene: &Arc<Scene>, shutter: (f64, f64)| {
+            let integrator: Arc<dyn Integrator + Send + Sync> = match photon_mapping {
+                Some((count, radius)) => Arc::new(PhotonMapIntegrator::new(
+                    scene, count, radius, max_depth, shutter,
+                )),
+                None => integrator.clone(),
+            };
+            match (&progressive, &metropolis) {
+                (Some(progressive), _) => {
+                    progressive.render(cam, scene, &settings, photon_passes, shutter)
                 }
+                (None, Some(metropolis)) => metropolis.render(cam, scene, &integrator, &settings),
+                (None, None) => render(cam, scene, &integrator, &settings),
             }
-            // Finish progress bar
-            bar.finish();
-            tx_k.send(ans_t).unwrap();
-        });
-    }
-    drop(tx);
-    for received in rx {
-        for i in 0..width {
-            for j in 0..height {
-                ans[i][j] += received[i][j];
+        };
+
+    let stem = path.strip_suffix(".jpg").unwrap_or(path);
+    match &animation {
+        None => {
+            let cam = make_camera(lookfrom, lookat, 0., 1.);
+            let film = render_film(&cam, &scene, (0., 1.));
+            if settings.adaptive.is_some() {
+                println!(
+                    "Average samples per pixel: {:.1}",
+                    film.average_sample_count()
+                );
+            }
+            println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
+            save_image(&film, path, quality);
+            save_aovs(&film, &aovs, path, quality);
+            if let Some(denoiser) = &denoiser {
+                let denoised_path = format!("{}_denoised.jpg", stem);
+                println!("Ouput denoised image as \"{}\"", denoised_path);
+                save_pixels(&denoiser.denoise(&film), &denoised_path, quality, true);
             }
         }
-    }
-
-    for (i, iter) in ans.iter().enumerate().take(width) {
-        for (j, jter) in iter.iter().enumerate().take(height) {
-            write_color(*jter, samples_per_pixel, &mut img, i, height - j - 1);
+        Some(animation) => {
+            // resume an interrupted sequence with e.g. `START_FRAME=42 cargo run --release`
+            let start_frame: usize = env::var("START_FRAME")
+                .ok()
+                .and_then(|frame| frame.parse().ok())
+                .unwrap_or(0);
+            for frame in start_frame..animation.frame_count {
+                let (time0, time1) = animation.shutter_interval(frame);
+                let cam = match &camera_path {
+                    Some(camera_path) => make_camera(
+                        camera_path.lookfrom.value(time0),
+                        camera_path.lookat.value(time0),
+                        time0,
+                        time1,
+                    ),
+                    None => make_camera(lookfrom, lookat, time0, time1),
+                };
+                let frame_scene = match &mut tlas {
+                    Some(tlas) => {
+                        tlas.update(time0, time1);
+                        Arc::new(scene.with_world(Arc::new(tlas.clone())))
+                    }
+                    None => scene.clone(),
+                };
+                let film = render_film(&cam, &frame_scene, (time0, time1));
+                let frame_path = format!("{}_{:04}.jpg", stem, frame);
+                println!("Ouput frame {} as \"{}\"", frame, frame_path);
+                match &denoiser {
+                    Some(denoiser) => {
+                        save_pixels(&denoiser.denoise(&film), &frame_path, quality, true)
+                    }
+                    None => save_image(&film, &frame_path, quality),
+                }
+            }
+            println!(" Author: {}", AUTHOR);
         }
     }
-
-    // Output image to file
-    println!("Ouput image as \"{}\"\n Author: {}", path, AUTHOR);
-    let output_image = image::DynamicImage::ImageRgb8(img);
-    let mut output_file = File::create(path).unwrap();
-    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
-        Ok(_) => {}
-        Err(_) => println!("Outputting image fails."),
-    }
 }
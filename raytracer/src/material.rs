@@ -1,9 +1,11 @@
 use std::{f64::consts::PI, sync::Arc};
 
+pub mod hair;
 pub mod texture;
 
 use crate::{
     hittable::HitRecord,
+    light::profile::EmissionProfile,
     pdf::{CosinePdf, Pdf},
     ray::Ray,
     rtweekend::random_double_unit,
@@ -12,9 +14,19 @@ use crate::{
 
 use texture::{SolidColor, Texture};
 
+/// The kind of scattering event, so that integrators can limit each kind.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Lobe {
+    Diffuse,
+    Specular, // reflection, including glossy metals
+    Transmission,
+    Volume,
+}
+
 pub struct ScatterRecord {
     pub specular_ray: Ray,
     pub is_specular: bool,
+    pub lobe: Lobe,
     pub attenuation: Color3,
     pub pdf_ptr: Option<Arc<dyn Pdf + Send + Sync>>,
 }
@@ -24,6 +36,7 @@ impl ScatterRecord {
         Self {
             specular_ray: (Ray::new(Vec3::zero(), Vec3::zero(), 0.)),
             is_specular: (false),
+            lobe: Lobe::Diffuse,
             attenuation: (Color3::zero()),
             pdf_ptr: (None),
         }
@@ -38,9 +51,27 @@ pub trait Material {
         0.
     }
 
+    /// The BSDF times the cosine towards `scattered`, after a scatter that
+    /// set `attenuation`. Only materials whose color changes with direction
+    /// need more than `attenuation * scattering_pdf`.
+    fn scattering(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: Color3,
+        scattered: &Ray,
+    ) -> Color3 {
+        attenuation * self.scattering_pdf(ray_in, rec, scattered)
+    }
+
     fn emitted(&self, _r_in: &Ray, _rec: &HitRecord, _u: f64, _v: f64, _p: &Point3) -> Color3 {
         Color3::zero()
     }
+
+    /// For the material ID render pass, 0 unless tagged by `MaterialId`.
+    fn id(&self) -> usize {
+        0
+    }
 }
 
 pub struct Lambertian {
@@ -62,6 +93,7 @@ impl Lambertian {
 impl Material for Lambertian {
     fn scatter(&self, _r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
         srec.is_specular = false;
+        srec.lobe = Lobe::Diffuse;
         srec.attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
         srec.pdf_ptr = Some(Arc::new(CosinePdf::new(&rec.normal)));
         true
@@ -101,6 +133,7 @@ impl Material for Metal {
         );
         srec.attenuation = self.albedo;
         srec.is_specular = true;
+        srec.lobe = Lobe::Specular;
         srec.pdf_ptr = None;
         true
     }
@@ -142,8 +175,10 @@ impl Material for Dielectric {
         let direction: Vec3 = if cannot_refract
             || Self::reflectance(cos_theta, refraction_ratio) > random_double_unit()
         {
+            srec.lobe = Lobe::Specular;
             Vec3::reflect(unit_direction, rec.normal)
         } else {
+            srec.lobe = Lobe::Transmission;
             Vec3::refract(unit_direction, rec.normal, refraction_ratio)
         };
         srec.specular_ray = Ray::new(rec.p, direction, r_in.time());
@@ -153,17 +188,39 @@ impl Material for Dielectric {
 
 pub struct DiffuseLight {
     emit: Arc<dyn Texture + Send + Sync>,
+    two_sided: bool,
+    profile: Option<Arc<EmissionProfile>>,
 }
 
 impl DiffuseLight {
     #[allow(dead_code)]
     pub fn new(emit: Arc<dyn Texture + Send + Sync>) -> Self {
-        Self { emit }
+        Self {
+            emit,
+            two_sided: false,
+            profile: None,
+        }
     }
 
     pub fn new_color(c: Color3) -> Self {
         Self {
             emit: Arc::new(SolidColor::new(c)),
+            two_sided: false,
+            profile: None,
+        }
+    }
+
+    /// An area light that may emit from both faces and whose radiance is
+    /// shaped by an angular profile measured from the surface normal.
+    pub fn new_full(
+        emit: Arc<dyn Texture + Send + Sync>,
+        two_sided: bool,
+        profile: Option<Arc<EmissionProfile>>,
+    ) -> Self {
+        Self {
+            emit,
+            two_sided,
+            profile,
         }
     }
 }
@@ -173,12 +230,16 @@ impl Material for DiffuseLight {
         false
     }
 
-    fn emitted(&self, _r_in: &Ray, rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color3 {
-        if rec.front_face {
-            self.emit.value(u, v, p)
-        } else {
-            Color3::zero()
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color3 {
+        if !rec.front_face && !self.two_sided {
+            return Color3::zero();
+        }
+        let mut emit = self.emit.value(u, v, p);
+        if let Some(profile) = &self.profile {
+            let cos_theta = (-r_in.direction().unit()) * rec.normal;
+            emit *= profile.value(cos_theta.clamp(-1., 1.).acos());
         }
+        emit
     }
 }
 
@@ -201,9 +262,51 @@ impl Isotropic {
 impl Material for Isotropic {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
         srec.is_specular = true;
+        srec.lobe = Lobe::Volume;
         srec.pdf_ptr = None;
         srec.specular_ray = Ray::new(rec.p, Vec3::random_in_unit_sphere(), r_in.time());
         srec.attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
         true
     }
 }
+
+/// Tags `ptr` with `id` for the material ID render pass, which stays the
+/// same from one render to the next.
+pub struct MaterialId {
+    ptr: Arc<dyn Material + Send + Sync>,
+    id: usize,
+}
+
+impl MaterialId {
+    pub fn new(ptr: Arc<dyn Material + Send + Sync>, id: usize) -> Self {
+        Self { ptr, id }
+    }
+}
+
+impl Material for MaterialId {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        self.ptr.scatter(r_in, rec, srec)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        self.ptr.scattering_pdf(ray_in, rec, scattered)
+    }
+
+    fn scattering(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: Color3,
+        scattered: &Ray,
+    ) -> Color3 {
+        self.ptr.scattering(ray_in, rec, attenuation, scattered)
+    }
+
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color3 {
+        self.ptr.emitted(r_in, rec, u, v, p)
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
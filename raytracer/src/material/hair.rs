@@ -0,0 +1,420 @@
+use std::{
+    f64::consts::{LN_2, PI},
+    sync::Arc,
+};
+
+use super::{Lobe, Material, ScatterRecord};
+use crate::{
+    hittable::HitRecord,
+    pdf::{onb::Onb, Pdf},
+    ray::Ray,
+    rtweekend::{degrees_to_radius, random_double_unit},
+    Color3, Vec3,
+};
+
+// reflection, transmission, and transmission with one internal reflection;
+// the lobe after those gathers all longer paths
+const P_MAX: usize = 3;
+
+/// Hair after Chiang et al., "A Practical and Controllable Hair and Fur Model
+/// for Production Path Tracing", as a fiber with a rough dielectric cuticle
+/// and an absorbing interior. It needs hits that record a tangent, as a
+/// `Curve` does, and reads the offset across the fiber from `v`.
+pub struct Hair {
+    sigma_a: Color3, // absorption per unit of fiber diameter
+    eta: f64,
+    v: [f64; P_MAX + 1], // longitudinal variance of each lobe
+    s: f64,              // azimuthal logistic scale
+    sin_2k_alpha: [f64; 3],
+    cos_2k_alpha: [f64; 3],
+}
+
+impl Hair {
+    /// `beta_m` and `beta_n` are the longitudinal and azimuthal roughness in
+    /// 0..1, and `alpha` the tilt of the cuticle scales in degrees.
+    pub fn new(sigma_a: Color3, beta_m: f64, beta_n: f64, alpha: f64) -> Self {
+        let v0 = (0.726 * beta_m + 0.812 * beta_m.powi(2) + 3.7 * beta_m.powi(20)).powi(2);
+        let s =
+            (PI / 8.).sqrt() * (0.265 * beta_n + 1.194 * beta_n.powi(2) + 5.372 * beta_n.powi(22));
+
+        let mut sin_2k_alpha = [degrees_to_radius(alpha).sin(), 0., 0.];
+        let mut cos_2k_alpha = [safe_sqrt(1. - sin_2k_alpha[0].powi(2)), 0., 0.];
+        for i in 1..3 {
+            sin_2k_alpha[i] = 2. * cos_2k_alpha[i - 1] * sin_2k_alpha[i - 1];
+            cos_2k_alpha[i] = cos_2k_alpha[i - 1].powi(2) - sin_2k_alpha[i - 1].powi(2);
+        }
+
+        Self {
+            sigma_a,
+            eta: 1.55,
+            v: [v0, 0.25 * v0, 4. * v0, 4. * v0],
+            s,
+            sin_2k_alpha,
+            cos_2k_alpha,
+        }
+    }
+
+    /// The absorption of natural hair with the given concentrations of the
+    /// dark and the reddish melanin, roughly 0 to 8 from blond to black.
+    pub fn from_melanin(eumelanin: f64, pheomelanin: f64, beta_m: f64, beta_n: f64) -> Self {
+        let sigma_a = eumelanin * Color3::new(0.419, 0.697, 1.37)
+            + pheomelanin * Color3::new(0.187, 0.4, 1.05);
+        Self::new(sigma_a, beta_m, beta_n, 2.)
+    }
+}
+
+fn safe_sqrt(x: f64) -> f64 {
+    x.max(0.).sqrt()
+}
+
+fn safe_asin(x: f64) -> f64 {
+    x.clamp(-1., 1.).asin()
+}
+
+/// The modified Bessel function of the first kind, by its series.
+fn i0(x: f64) -> f64 {
+    let mut sum = 0.;
+    let mut term = 1.;
+    let x2 = x * x / 4.;
+    for i in 1..=10 {
+        sum += term;
+        term *= x2 / (i * i) as f64;
+    }
+    sum
+}
+
+fn log_i0(x: f64) -> f64 {
+    if x > 12. {
+        x + 0.5 * (-(2. * PI).ln() + (1. / x).ln() + 1. / (8. * x))
+    } else {
+        i0(x).ln()
+    }
+}
+
+/// The longitudinal scattering function.
+fn mp(cos_theta_i: f64, cos_theta_o: f64, sin_theta_i: f64, sin_theta_o: f64, v: f64) -> f64 {
+    let a = cos_theta_i * cos_theta_o / v;
+    let b = sin_theta_i * sin_theta_o / v;
+    if v <= 0.1 {
+        (log_i0(a) - b - 1. / v + LN_2 + (1. / (2. * v)).ln()).exp()
+    } else {
+        (-b).exp() * i0(a) / ((1. / v).sinh() * 2. * v)
+    }
+}
+
+fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let cos_theta_i = cos_theta_i.clamp(-1., 1.);
+    let sin_theta_t = safe_sqrt(1. - cos_theta_i * cos_theta_i) / eta;
+    if sin_theta_t >= 1. {
+        return 1.;
+    }
+    let cos_theta_t = safe_sqrt(1. - sin_theta_t * sin_theta_t);
+    let parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (parallel * parallel + perpendicular * perpendicular) / 2.
+}
+
+fn logistic(x: f64, s: f64) -> f64 {
+    let x = x.abs();
+    (-x / s).exp() / (s * (1. + (-x / s).exp()).powi(2))
+}
+
+fn logistic_cdf(x: f64, s: f64) -> f64 {
+    1. / (1. + (-x / s).exp())
+}
+
+fn trimmed_logistic(x: f64, s: f64, a: f64, b: f64) -> f64 {
+    logistic(x, s) / (logistic_cdf(b, s) - logistic_cdf(a, s))
+}
+
+fn sample_trimmed_logistic(u: f64, s: f64, a: f64, b: f64) -> f64 {
+    let k = logistic_cdf(b, s) - logistic_cdf(a, s);
+    let x = -s * (1. / (u * k + logistic_cdf(a, s)) - 1.).ln();
+    x.clamp(a, b)
+}
+
+/// The azimuthal angle a path of `p` internal segments leaves at.
+fn phi(p: usize, gamma_o: f64, gamma_t: f64) -> f64 {
+    2. * p as f64 * gamma_t - 2. * gamma_o + p as f64 * PI
+}
+
+/// The azimuthal scattering function.
+fn np(dphi: f64, p: usize, s: f64, gamma_o: f64, gamma_t: f64) -> f64 {
+    let mut d = dphi - phi(p, gamma_o, gamma_t);
+    while d > PI {
+        d -= 2. * PI;
+    }
+    while d < -PI {
+        d += 2. * PI;
+    }
+    trimmed_logistic(d, s, -PI, PI)
+}
+
+fn luminance(c: Color3) -> f64 {
+    (c.x() + c.y() + c.z()) / 3.
+}
+
+/// One hit on a fiber seen from one direction: the fiber's frame, with x
+/// along it and z facing back along the incoming ray, and everything about
+/// the path through the fiber that doesn't depend on where light goes next.
+struct HairPoint {
+    frame: Onb,
+    sin_theta_o: f64,
+    cos_theta_o: f64,
+    phi_o: f64,
+    gamma_o: f64,
+    gamma_t: f64,
+    ap: [Color3; P_MAX + 1],
+    ap_pdf: [f64; P_MAX + 1],
+    v: [f64; P_MAX + 1],
+    s: f64,
+    sin_2k_alpha: [f64; 3],
+    cos_2k_alpha: [f64; 3],
+}
+
+impl HairPoint {
+    fn new(hair: &Hair, r_in: &Ray, rec: &HitRecord) -> Self {
+        // shapes without a direction get an arbitrary one across the normal
+        let x = if rec.tangent.near_zero() {
+            Onb::build_from_w(rec.normal).u()
+        } else {
+            rec.tangent.unit()
+        };
+        let wo = -r_in.direction().unit();
+        let mut z = wo - (wo * x) * x;
+        if z.near_zero() {
+            // looking straight down the fiber
+            z = rec.normal - (rec.normal * x) * x;
+        }
+        let z = z.unit();
+        let frame = Onb::from_axes(x, Vec3::cross(z, x), z);
+        let wo = frame.to_local(&wo);
+
+        let sin_theta_o = wo.x().clamp(-1., 1.);
+        let cos_theta_o = safe_sqrt(1. - sin_theta_o * sin_theta_o);
+        let phi_o = wo.z().atan2(wo.y());
+        let h = 2. * rec.v - 1.;
+        let gamma_o = safe_asin(h);
+
+        // the refracted path inside the fiber
+        let sin_theta_t = sin_theta_o / hair.eta;
+        let cos_theta_t = safe_sqrt(1. - sin_theta_t * sin_theta_t);
+        let etap = (hair.eta * hair.eta - sin_theta_o * sin_theta_o).sqrt() / cos_theta_o;
+        let sin_gamma_t = h / etap;
+        let cos_gamma_t = safe_sqrt(1. - sin_gamma_t * sin_gamma_t);
+        let gamma_t = safe_asin(sin_gamma_t);
+        let distance = 2. * cos_gamma_t / cos_theta_t;
+        let t = Color3::new(
+            (-hair.sigma_a.x() * distance).exp(),
+            (-hair.sigma_a.y() * distance).exp(),
+            (-hair.sigma_a.z() * distance).exp(),
+        );
+
+        // how much each lobe carries
+        let f = fresnel_dielectric(cos_theta_o * safe_sqrt(1. - h * h), hair.eta);
+        let mut ap = [Color3::zero(); P_MAX + 1];
+        ap[0] = Color3::ones() * f;
+        ap[1] = (1. - f) * (1. - f) * t;
+        ap[2] = f * Vec3::elemul(ap[1], t);
+        let denominator = Color3::ones() - f * t;
+        ap[3] = f * Vec3::elemul(ap[2], t);
+        for a in 0..3 {
+            ap[3][a] = if denominator[a] > 0. {
+                ap[3][a] / denominator[a]
+            } else {
+                0.
+            };
+        }
+        let total: f64 = ap.iter().map(|&c| luminance(c)).sum();
+        let mut ap_pdf = [0.; P_MAX + 1];
+        if total > 0. {
+            for (pdf, &c) in ap_pdf.iter_mut().zip(ap.iter()) {
+                *pdf = luminance(c) / total;
+            }
+        }
+
+        Self {
+            frame,
+            sin_theta_o,
+            cos_theta_o,
+            phi_o,
+            gamma_o,
+            gamma_t,
+            ap,
+            ap_pdf,
+            v: hair.v,
+            s: hair.s,
+            sin_2k_alpha: hair.sin_2k_alpha,
+            cos_2k_alpha: hair.cos_2k_alpha,
+        }
+    }
+
+    /// The outgoing longitudinal angle of lobe `p`, shifted by the tilted
+    /// cuticle scales.
+    fn tilted(&self, p: usize) -> (f64, f64) {
+        let (so, co) = (self.sin_theta_o, self.cos_theta_o);
+        let (sin, cos) = match p {
+            0 => (
+                so * self.cos_2k_alpha[1] - co * self.sin_2k_alpha[1],
+                co * self.cos_2k_alpha[1] + so * self.sin_2k_alpha[1],
+            ),
+            1 => (
+                so * self.cos_2k_alpha[0] + co * self.sin_2k_alpha[0],
+                co * self.cos_2k_alpha[0] - so * self.sin_2k_alpha[0],
+            ),
+            2 => (
+                so * self.cos_2k_alpha[2] + co * self.sin_2k_alpha[2],
+                co * self.cos_2k_alpha[2] - so * self.sin_2k_alpha[2],
+            ),
+            _ => (so, co),
+        };
+        (sin, cos.abs())
+    }
+
+    /// The angles of world direction `wi` in the fiber's frame.
+    fn angles(&self, wi: &Vec3) -> (f64, f64, f64) {
+        let wi = self.frame.to_local(&wi.unit());
+        let sin_theta_i = wi.x().clamp(-1., 1.);
+        let cos_theta_i = safe_sqrt(1. - sin_theta_i * sin_theta_i);
+        let phi_i = wi.z().atan2(wi.y());
+        (sin_theta_i, cos_theta_i, phi_i - self.phi_o)
+    }
+
+    /// Each lobe's density over directions, weighted by `weight`.
+    fn sum_lobes<T>(&self, wi: &Vec3, weight: impl Fn(usize) -> T) -> T
+    where
+        T: std::ops::Add<Output = T> + std::ops::Mul<f64, Output = T>,
+    {
+        let (sin_theta_i, cos_theta_i, dphi) = self.angles(wi);
+        let mut sum = weight(P_MAX)
+            * (mp(
+                cos_theta_i,
+                self.cos_theta_o,
+                sin_theta_i,
+                self.sin_theta_o,
+                self.v[P_MAX],
+            ) / (2. * PI));
+        for p in 0..P_MAX {
+            let (sin_theta_op, cos_theta_op) = self.tilted(p);
+            sum = sum
+                + weight(p)
+                    * (mp(
+                        cos_theta_i,
+                        cos_theta_op,
+                        sin_theta_i,
+                        sin_theta_op,
+                        self.v[p],
+                    ) * np(dphi, p, self.s, self.gamma_o, self.gamma_t));
+        }
+        sum
+    }
+
+    /// The BSDF times the cosine to the fiber's normal.
+    fn f_cos(&self, wi: &Vec3) -> Color3 {
+        self.sum_lobes(wi, |p| self.ap[p])
+    }
+
+    fn pdf(&self, wi: &Vec3) -> f64 {
+        self.sum_lobes(wi, |p| self.ap_pdf[p])
+    }
+
+    /// Picks a lobe by how much it carries, then a direction from it.
+    fn sample(&self) -> Vec3 {
+        let mut u = random_double_unit();
+        let mut p = 0;
+        while p < P_MAX && u >= self.ap_pdf[p] {
+            u -= self.ap_pdf[p];
+            p += 1;
+        }
+        let (sin_theta_op, cos_theta_op) = self.tilted(p);
+
+        let v = self.v[p];
+        let u1 = random_double_unit().max(1e-5);
+        let cos_theta = 1. + v * (u1 + (1. - u1) * (-2. / v).exp()).ln();
+        let sin_theta = safe_sqrt(1. - cos_theta * cos_theta);
+        let cos_phi = (2. * PI * random_double_unit()).cos();
+        let sin_theta_i = -cos_theta * sin_theta_op + sin_theta * cos_phi * cos_theta_op;
+        let cos_theta_i = safe_sqrt(1. - sin_theta_i * sin_theta_i);
+
+        let u2 = random_double_unit();
+        let dphi = if p < P_MAX {
+            phi(p, self.gamma_o, self.gamma_t) + sample_trimmed_logistic(u2, self.s, -PI, PI)
+        } else {
+            2. * PI * u2
+        };
+        let phi_i = self.phi_o + dphi;
+        self.frame.local(
+            sin_theta_i,
+            cos_theta_i * phi_i.cos(),
+            cos_theta_i * phi_i.sin(),
+        )
+    }
+}
+
+struct HairPdf {
+    point: HairPoint,
+}
+
+impl Pdf for HairPdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        self.point.pdf(direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.point.sample()
+    }
+}
+
+impl Material for Hair {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        srec.is_specular = false;
+        srec.lobe = Lobe::Diffuse;
+        // the color depends on the direction, see `scattering`
+        srec.attenuation = Color3::ones();
+        srec.pdf_ptr = Some(Arc::new(HairPdf {
+            point: HairPoint::new(self, r_in, rec),
+        }));
+        true
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        HairPoint::new(self, ray_in, rec).pdf(&scattered.direction())
+    }
+
+    fn scattering(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        _attenuation: Color3,
+        scattered: &Ray,
+    ) -> Color3 {
+        HairPoint::new(self, ray_in, rec).f_cos(&scattered.direction())
+    }
+}
+
+#[test]
+fn test_hair_energy() {
+    use crate::hittable::HitRecord;
+    use crate::Point3;
+
+    // no absorption: the lobes sample to an average weight of about one
+    let hair = Hair::new(Color3::zero(), 0.3, 0.3, 2.);
+    let mut rec = HitRecord::new();
+    rec.tangent = Vec3::new(1., 0., 0.);
+    rec.normal = Vec3::new(0., 0., 1.);
+    let r_in = Ray::new(Point3::new(0.3, 0., 1.), Vec3::new(-0.3, 0., -1.), 0.);
+    let n = 20000;
+    let mut sum = Color3::zero();
+    for i in 0..n {
+        rec.v = (i as f64 + 0.5) / n as f64;
+        let point = HairPoint::new(&hair, &r_in, &rec);
+        let wi = point.sample();
+        let pdf = point.pdf(&wi);
+        if pdf > 0. {
+            sum += point.f_cos(&wi) / pdf;
+        }
+    }
+    let average = sum / n as f64;
+    assert!((average.x() - 1.).abs() < 0.05);
+    assert!((average.x() - average.z()).abs() < 1e-9);
+}
@@ -27,6 +27,24 @@ impl Texture for SolidColor {
     }
 }
 
+/// Colors given at the corners of a `Triangle`, blended across it by its
+/// barycentric UVs.
+pub struct VertexColors {
+    colors: [Color3; 3],
+}
+
+impl VertexColors {
+    pub fn new(a: Color3, b: Color3, c: Color3) -> Self {
+        Self { colors: [a, b, c] }
+    }
+}
+
+impl Texture for VertexColors {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color3 {
+        (1. - u - v) * self.colors[0] + u * self.colors[1] + v * self.colors[2]
+    }
+}
+
 pub struct CheckerTexture {
     odd: Arc<dyn Texture + Send + Sync>,
     even: Arc<dyn Texture + Send + Sync>,
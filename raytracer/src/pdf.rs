@@ -2,13 +2,16 @@ pub mod onb;
 
 use std::{f64::consts::PI, sync::Arc};
 
-use crate::{hittable::Hittable, rtweekend::random_double_unit, Point3, Vec3};
+use crate::{
+    hittable::Hittable,
+    rtweekend::{random_double_2d, random_double_unit},
+    Point3, Vec3,
+};
 
 use self::onb::Onb;
 
 pub fn random_cosine_direction() -> Vec3 {
-    let r1 = random_double_unit();
-    let r2 = random_double_unit();
+    let (r1, r2) = random_double_2d();
     let z = (1. - r2).sqrt();
 
     let phi = 2. * PI * r1;
@@ -19,8 +22,7 @@ pub fn random_cosine_direction() -> Vec3 {
 }
 
 pub fn random_to_sphere(radius: f64, distance_squared: f64) -> Vec3 {
-    let r1 = random_double_unit();
-    let r2 = random_double_unit();
+    let (r1, r2) = random_double_2d();
     let z = 1. + r2 * ((1. - radius * radius / distance_squared).sqrt() - 1.);
 
     let phi = 2. * PI * r1;
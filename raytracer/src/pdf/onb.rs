@@ -28,6 +28,16 @@ impl Onb {
         a.x() * self.u() + a.y() * self.v() + a.z() * self.w()
     }
 
+    /// The coordinates of world space `a` in this basis, undoing `local_vec`.
+    pub fn to_local(&self, a: &Vec3) -> Vec3 {
+        Vec3::new(*a * self.u(), *a * self.v(), *a * self.w())
+    }
+
+    /// A basis from three given orthonormal axes.
+    pub fn from_axes(u: Vec3, v: Vec3, w: Vec3) -> Self {
+        Self { axis: [u, v, w] }
+    }
+
     pub fn build_from_w(n: Vec3) -> Self {
         let mut axis = [Vec3::zero(); 3];
         axis[2] = n.unit();
@@ -0,0 +1,416 @@
+pub mod adaptive;
+pub mod aov;
+pub mod denoise;
+pub mod features;
+pub mod film;
+pub mod filter;
+pub mod integrator;
+pub mod metropolis;
+pub mod scene;
+
+use std::{
+    fs::File,
+    ops::{Add, AddAssign, Div, Mul},
+    sync::{mpsc, Arc},
+    thread,
+};
+
+use image::{ImageBuffer, RgbImage};
+use indicatif::{MultiProgress, ProgressBar};
+
+use self::{
+    adaptive::{AdaptiveSampling, PixelStats},
+    aov::Aov,
+    features::Features,
+    film::Film,
+    filter::Filter,
+    integrator::Integrator,
+    scene::Scene,
+};
+use crate::{
+    camera::Camera,
+    color::{luminance, write_color, write_data},
+    rtweekend::random_double_2d,
+    sampler::{self, Sampler},
+    Color3,
+};
+
+pub const THREAD_NUM: usize = 4;
+
+pub struct RenderSettings {
+    pub width: usize,
+    pub height: usize,
+    pub samples_per_pixel: usize,
+    pub filter: Arc<dyn Filter + Send + Sync>,
+    pub sampler: Box<dyn Sampler + Send + Sync>,
+    pub adaptive: Option<AdaptiveSampling>, // with `samples_per_pixel` as the budget
+    pub features: bool,                     // record first-hit features for the denoiser and AOVs
+    pub clamp: Option<f64>,                 // max luminance of a single sample
+    pub hide_progress: bool,
+}
+
+/// Radiance arriving along a camera path, split by how many bounces it took.
+#[derive(Clone, Copy)]
+pub struct Radiance {
+    pub emitted: Color3,  // by the first surface hit, or the background
+    pub direct: Color3,   // after one bounce
+    pub indirect: Color3, // after two or more bounces
+}
+
+impl Radiance {
+    pub fn new(emitted: Color3, direct: Color3, indirect: Color3) -> Self {
+        Self {
+            emitted,
+            direct,
+            indirect,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self::new(Color3::zero(), Color3::zero(), Color3::zero())
+    }
+
+    pub fn total(&self) -> Color3 {
+        self.emitted + self.direct + self.indirect
+    }
+
+    /// Adds light that reached the camera after `bounces` non-specular bounces.
+    pub fn accumulate(&mut self, bounces: usize, light: Color3) {
+        match bounces {
+            0 => self.emitted += light,
+            1 => self.direct += light,
+            _ => self.indirect += light,
+        }
+    }
+
+    pub fn is_finite(&self) -> bool {
+        [self.emitted, self.direct, self.indirect]
+            .iter()
+            .all(|c| c.x().is_finite() && c.y().is_finite() && c.z().is_finite())
+    }
+
+    /// Scales the sample down to at most `max_luminance`, keeping its hue.
+    pub fn clamp(self, max_luminance: f64) -> Self {
+        let l = luminance(self.total());
+        if l > max_luminance {
+            self * (max_luminance / l)
+        } else {
+            self
+        }
+    }
+}
+
+impl From<Color3> for Radiance {
+    fn from(color: Color3) -> Self {
+        Self::new(color, Color3::zero(), Color3::zero())
+    }
+}
+
+impl Add for Radiance {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(
+            self.emitted + other.emitted,
+            self.direct + other.direct,
+            self.indirect + other.indirect,
+        )
+    }
+}
+
+impl AddAssign for Radiance {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Mul<f64> for Radiance {
+    type Output = Self;
+
+    fn mul(self, other: f64) -> Self {
+        Self::new(
+            self.emitted * other,
+            self.direct * other,
+            self.indirect * other,
+        )
+    }
+}
+
+impl Div<f64> for Radiance {
+    type Output = Self;
+
+    fn div(self, other: f64) -> Self {
+        self * (1. / other)
+    }
+}
+
+/// Applies the optional clamp, or records and drops a NaN or infinite
+/// sample, since a single one would poison the whole pixel.
+fn sanitize(
+    film: &mut Film,
+    radiance: Radiance,
+    i: usize,
+    j: usize,
+    clamp: Option<f64>,
+) -> Option<Radiance> {
+    if !radiance.is_finite() {
+        film.add_invalid_sample(i, j);
+        return None;
+    }
+    Some(match clamp {
+        Some(max_luminance) => radiance.clamp(max_luminance),
+        None => radiance,
+    })
+}
+
+/// Renders one image.
+pub fn render(
+    cam: &Arc<dyn Camera + Send + Sync>,
+    scene: &Arc<Scene>,
+    integrator: &Arc<dyn Integrator + Send + Sync>,
+    settings: &RenderSettings,
+) -> Film {
+    let width = settings.width;
+    let height = settings.height;
+    let samples_per_pixel = settings.samples_per_pixel;
+
+    // Progress bar UI powered by library `indicatif`
+    // You can use indicatif::ProgressStyle to make it more beautiful
+    // You can also use indicatif::MultiProgress in multi-threading to show progress of each thread
+    let bar_collection = MultiProgress::new();
+    let bars: Vec<ProgressBar> = (0..THREAD_NUM)
+        .map(|_| {
+            bar_collection.add(if settings.hide_progress {
+                ProgressBar::hidden()
+            } else {
+                ProgressBar::new((height * width * samples_per_pixel / THREAD_NUM) as u64)
+            })
+        })
+        .collect();
+
+    let mut film = Film::new(width, height, settings.filter.clone());
+    let mut stats = vec![vec![PixelStats::new(); height]; width];
+    let mut taken = vec![vec![0; height]; width];
+    match settings.adaptive {
+        None => {
+            let samples = vec![vec![samples_per_pixel; height]; width];
+            let pass = Pass { samples, taken };
+            render_pass(
+                cam, scene, integrator, settings, &bars, pass, &mut film, &mut stats,
+            );
+        }
+        Some(adaptive) => {
+            // the minimum everywhere first, then passes over the pixels that are
+            // still noisy, each at most doubling their samples, until the budget
+            // of `samples_per_pixel` on average is spent
+            let budget = width * height * samples_per_pixel;
+            let max_samples = adaptive.max_samples(samples_per_pixel);
+            let first = adaptive.min_samples.min(samples_per_pixel).max(1);
+            let mut samples = vec![vec![first; height]; width];
+            let mut spent = 0;
+            loop {
+                spent += samples.iter().flatten().sum::<usize>();
+                let pass = Pass {
+                    samples: samples.clone(),
+                    taken: taken.clone(),
+                };
+                render_pass(
+                    cam, scene, integrator, settings, &bars, pass, &mut film, &mut stats,
+                );
+                for (column, added) in taken.iter_mut().zip(samples.iter()) {
+                    for (t, n) in column.iter_mut().zip(added.iter()) {
+                        *t += n;
+                    }
+                }
+
+                match adaptive.next_pass(&stats, &taken, max_samples, budget.saturating_sub(spent))
+                {
+                    Some(next) => samples = next,
+                    None => break,
+                }
+            }
+        }
+    }
+    for bar in &bars {
+        bar.finish();
+    }
+
+    let invalid = film.invalid_samples();
+    if !invalid.is_empty() {
+        println!(
+            "Discarded {} NaN/Inf samples, e.g. at pixels {:?}",
+            film.invalid_sample_count(),
+            invalid
+        );
+    }
+    film
+}
+
+/// How many samples every pixel takes in one pass, and how many it took
+/// before, so that the sample indices never repeat.
+struct Pass {
+    samples: Vec<Vec<usize>>,
+    taken: Vec<Vec<usize>>,
+}
+
+/// Takes the pass's samples with the work of every pixel split between the
+/// threads, and adds them to `film` and to the pixels' luminance `stats`.
+#[allow(clippy::too_many_arguments)]
+fn render_pass(
+    cam: &Arc<dyn Camera + Send + Sync>,
+    scene: &Arc<Scene>,
+    integrator: &Arc<dyn Integrator + Send + Sync>,
+    settings: &RenderSettings,
+    bars: &[ProgressBar],
+    pass: Pass,
+    film: &mut Film,
+    stats: &mut [Vec<PixelStats>],
+) {
+    let width = settings.width;
+    let height = settings.height;
+    let record_features = settings.features;
+    let clamp = settings.clamp;
+    let pass = Arc::new(pass);
+
+    let (tx, rx) = mpsc::channel();
+    for (k, bar) in bars.iter().enumerate() {
+        let sampler_t = settings.sampler.clone_box();
+        let camm = cam.clone();
+        let scene_t = scene.clone();
+        let integrator_t = integrator.clone();
+        let pass_t = pass.clone();
+        let tx_k = tx.clone();
+        let mut film_t = Film::new(width, height, settings.filter.clone());
+        let bar = bar.clone();
+        thread::spawn(move || {
+            sampler::install(sampler_t);
+            let mut stats_t = vec![vec![PixelStats::new(); height]; width];
+            let mut splats = Vec::new();
+            for (i, column) in stats_t.iter_mut().enumerate() {
+                for (j, pixel_stats) in column.iter_mut().enumerate() {
+                    // threads take disjoint runs of the pixel's sample indices
+                    let n = pass_t.samples[i][j];
+                    let (first, last) = (n * k / THREAD_NUM, n * (k + 1) / THREAD_NUM);
+                    let mut valid = 0;
+                    for s in first..last {
+                        sampler::start_pixel_sample(i, j, pass_t.taken[i][j] + s);
+                        let (dx, dy) = random_double_2d();
+                        let x = i as f64 + dx;
+                        let y = j as f64 + dy;
+                        let r = camm.get_ray(x / width as f64, y / height as f64);
+                        let color = match r {
+                            Some(r) => integrator_t.li(r, &scene_t, camm.as_ref(), &mut splats),
+                            None => Radiance::zero(),
+                        };
+                        let color = sanitize(&mut film_t, color, i, j, clamp);
+                        for splat in splats.drain(..) {
+                            let (si, sj) = splat.pixel(width, height);
+                            if let Some(radiance) =
+                                sanitize(&mut film_t, splat.radiance, si, sj, clamp)
+                            {
+                                film_t.add_splat(
+                                    splat.s * width as f64,
+                                    splat.t * height as f64,
+                                    radiance,
+                                );
+                            }
+                        }
+                        let color = match color {
+                            Some(color) => color,
+                            None => continue,
+                        };
+
+                        if record_features {
+                            let features = match r {
+                                Some(r) => Features::from_ray(&r, &scene_t),
+                                None => Features::zero(),
+                            };
+                            film_t.add_features(i, j, features);
+                        }
+                        film_t.add_sample(x, y, color);
+                        pixel_stats.add(luminance(color.total()));
+                        valid += 1;
+                    }
+                    film_t.add_sample_count(i, j, valid);
+                    bar.inc((last - first) as u64);
+                }
+            }
+            tx_k.send((film_t, stats_t)).unwrap();
+        });
+    }
+    drop(tx);
+    for (received, received_stats) in rx {
+        film.merge(&received);
+        for (column, received_column) in stats.iter_mut().zip(received_stats.iter()) {
+            for (pixel, received_pixel) in column.iter_mut().zip(received_column.iter()) {
+                pixel.merge(received_pixel);
+            }
+        }
+    }
+}
+
+pub fn save_image(film: &Film, path: &str, quality: u8) {
+    let pixels: Vec<Vec<Color3>> = (0..film.width())
+        .map(|i| (0..film.height()).map(|j| film.pixel(i, j)).collect())
+        .collect();
+    save_pixels(&pixels, path, quality, true);
+}
+
+/// Saves an image indexed like the film, as a lossless PNG if `path` ends in
+/// `.png` and a JPEG otherwise. Set `gamma` for colors and clear it for data,
+/// which should already be mapped into [0, 1].
+pub fn save_pixels(pixels: &[Vec<Color3>], path: &str, quality: u8, gamma: bool) {
+    let width = pixels.len();
+    let height = pixels[0].len();
+
+    // Create image data
+    let mut img: RgbImage = ImageBuffer::new(width.try_into().unwrap(), height.try_into().unwrap());
+    for (i, column) in pixels.iter().enumerate() {
+        for (j, pixel) in column.iter().enumerate() {
+            if gamma {
+                write_color(*pixel, &mut img, i, height - j - 1);
+            } else {
+                write_data(*pixel, &mut img, i, height - j - 1);
+            }
+        }
+    }
+
+    // Output image to file
+    let output_image = image::DynamicImage::ImageRgb8(img);
+    let mut output_file = File::create(path).unwrap();
+    let format = if path.ends_with(".png") {
+        image::ImageOutputFormat::Png
+    } else {
+        image::ImageOutputFormat::Jpeg(quality)
+    };
+    match output_image.write_to(&mut output_file, format) {
+        Ok(_) => {}
+        Err(_) => println!("Outputting image fails."),
+    }
+}
+
+/// Saves each render pass next to `path`, e.g. `test_albedo.jpg` for
+/// `test.jpg`. Data passes go to PNG, e.g. `test_depth.png`, so that
+/// compression doesn't smear the edges between IDs.
+pub fn save_aovs(film: &Film, aovs: &[Aov], path: &str, quality: u8) {
+    let stem = path.strip_suffix(".jpg").unwrap_or(path);
+    for aov in aovs {
+        let extension = if aov.is_color() { "jpg" } else { "png" };
+        let aov_path = format!("{}_{}.{}", stem, aov.name(), extension);
+        save_pixels(&aov.extract(film), &aov_path, quality, aov.is_color());
+    }
+}
+
+#[test]
+fn test_radiance_clamp() {
+    let r = Radiance::new(Color3::ones() * 10., Color3::ones() * 10., Color3::zero());
+    let clamped = r.clamp(5.);
+    assert!((luminance(clamped.total()) - 5.).abs() < 1e-9);
+    assert_eq!(clamped.emitted, clamped.direct);
+    assert!(!Radiance::new(
+        Color3::new(f64::NAN, 0., 0.),
+        Color3::zero(),
+        Color3::zero()
+    )
+    .is_finite());
+}
@@ -0,0 +1,159 @@
+/// Stop sampling a pixel once its estimate is good enough, and spend the
+/// samples saved there on the pixels that are still noisy. The noise is the
+/// standard error of the pixel's luminance after gamma correction, so the
+/// same threshold means about the same visible grain in dark and bright areas.
+#[derive(Clone, Copy)]
+pub struct AdaptiveSampling {
+    pub min_samples: usize,
+    pub noise_threshold: f64,
+    pub max_samples: Option<usize>, // per pixel, four times the budget if unset
+}
+
+impl AdaptiveSampling {
+    pub fn new(min_samples: usize, noise_threshold: f64) -> Self {
+        Self {
+            min_samples,
+            noise_threshold,
+            max_samples: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_max_samples(mut self, max_samples: usize) -> Self {
+        self.max_samples = Some(max_samples);
+        self
+    }
+
+    /// The most samples one pixel may take when the average is `budget`.
+    pub fn max_samples(&self, budget: usize) -> usize {
+        self.max_samples.unwrap_or(4 * budget).max(self.min_samples)
+    }
+
+    pub fn converged(&self, stats: &PixelStats) -> bool {
+        if stats.count < self.min_samples.max(2) {
+            return false;
+        }
+        // d sqrt(L) = dL / (2 sqrt(L))
+        let error = (stats.variance() / stats.count as f64).sqrt();
+        error / (2. * stats.mean.max(1e-4).sqrt()) <= self.noise_threshold
+    }
+
+    /// How many more samples each pixel takes in the next pass, each noisy
+    /// one at most doubling, with `left` samples of the budget to spend.
+    /// `None` once no pixel is left to sample or the rest won't buy every
+    /// noisy pixel a sample.
+    pub fn next_pass(
+        &self,
+        stats: &[Vec<PixelStats>],
+        taken: &[Vec<usize>],
+        max_samples: usize,
+        left: usize,
+    ) -> Option<Vec<Vec<usize>>> {
+        let mut noisy = Vec::new();
+        for (i, column) in stats.iter().enumerate() {
+            for (j, pixel_stats) in column.iter().enumerate() {
+                if taken[i][j] < max_samples && !self.converged(pixel_stats) {
+                    noisy.push((i, j));
+                }
+            }
+        }
+        if noisy.is_empty() {
+            return None;
+        }
+        let share = left / noisy.len();
+        if share == 0 {
+            return None;
+        }
+        let mut samples = vec![vec![0; taken[0].len()]; taken.len()];
+        for (i, j) in noisy {
+            samples[i][j] = taken[i][j].min(share).min(max_samples - taken[i][j]);
+        }
+        Some(samples)
+    }
+}
+
+/// Running mean and variance of the luminance of a pixel's samples (Welford).
+#[derive(Clone, Copy)]
+pub struct PixelStats {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl PixelStats {
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            mean: 0.,
+            m2: 0.,
+        }
+    }
+
+    pub fn add(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// Adds the samples gathered in `other`, e.g. by another thread.
+    pub fn merge(&mut self, other: &PixelStats) {
+        if other.count == 0 {
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.m2 += other.m2 + delta * delta * (self.count * other.count) as f64 / count as f64;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.count = count;
+    }
+
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.
+        } else {
+            self.m2 / (self.count - 1) as f64
+        }
+    }
+}
+
+#[test]
+fn test_pixel_stats() {
+    let mut stats = PixelStats::new();
+    for x in [1., 2., 3., 4.] {
+        stats.add(x);
+    }
+    assert_eq!(stats.mean, 2.5);
+    assert!((stats.variance() - 5. / 3.).abs() < 1e-12);
+
+    // the same samples split between two threads
+    let mut first = PixelStats::new();
+    first.add(1.);
+    let mut second = PixelStats::new();
+    for x in [2., 3., 4.] {
+        second.add(x);
+    }
+    first.merge(&second);
+    assert_eq!(first.count, 4);
+    assert!((first.mean - 2.5).abs() < 1e-12);
+    assert!((first.variance() - 5. / 3.).abs() < 1e-12);
+
+    let adaptive = AdaptiveSampling::new(4, 0.01);
+    let mut black = PixelStats::new();
+    for _ in 0..4 {
+        black.add(0.);
+    }
+    assert!(adaptive.converged(&black));
+    assert!(!adaptive.converged(&stats));
+
+    // every pixel converged after the minimum, with budget left over
+    let stats = vec![vec![black; 2]; 3];
+    let taken = vec![vec![4; 2]; 3];
+    assert!(adaptive.next_pass(&stats, &taken, 64, 1000).is_none());
+
+    let mut noisy = stats.clone();
+    noisy[1][0] = first;
+    let samples = adaptive.next_pass(&noisy, &taken, 64, 1000).unwrap();
+    assert_eq!(samples.iter().flatten().sum::<usize>(), 4);
+    assert_eq!(samples[1][0], 4);
+}
@@ -0,0 +1,120 @@
+use super::{features::Features, film::Film};
+use crate::{sampler::mix_bits, Color3, Vec3};
+
+/// Arbitrary output variables, each written as its own image next to the
+/// beauty pass.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aov {
+    Albedo,
+    Depth, // nearer is brighter
+    Normal,
+    Uv,
+    MaterialId,
+    ObjectId,
+    Emission,
+    Direct,
+    Indirect,
+    SampleCount,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 10] = [
+        Aov::Albedo,
+        Aov::Depth,
+        Aov::Normal,
+        Aov::Uv,
+        Aov::MaterialId,
+        Aov::ObjectId,
+        Aov::Emission,
+        Aov::Direct,
+        Aov::Indirect,
+        Aov::SampleCount,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Albedo => "albedo",
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Uv => "uv",
+            Aov::MaterialId => "material_id",
+            Aov::ObjectId => "object_id",
+            Aov::Emission => "emission",
+            Aov::Direct => "direct",
+            Aov::Indirect => "indirect",
+            Aov::SampleCount => "sample_count",
+        }
+    }
+
+    /// Whether the pass holds colors, which get gamma corrected on output.
+    pub fn is_color(&self) -> bool {
+        matches!(
+            self,
+            Aov::Albedo | Aov::Emission | Aov::Direct | Aov::Indirect
+        )
+    }
+
+    /// The pass as an image indexed like the film. Data passes are mapped
+    /// into [0, 1].
+    pub fn extract(&self, film: &Film) -> Vec<Vec<Color3>> {
+        let width = film.width();
+        let height = film.height();
+        let pass = |f: &dyn Fn(usize, usize) -> Color3| -> Vec<Vec<Color3>> {
+            (0..width)
+                .map(|i| (0..height).map(|j| f(i, j)).collect())
+                .collect()
+        };
+        let features = |i: usize, j: usize| -> Features { film.features(i, j) };
+
+        match self {
+            Aov::Albedo => pass(&|i, j| features(i, j).albedo),
+            Aov::Depth => {
+                let max_depth = (0..width)
+                    .flat_map(|i| (0..height).map(move |j| (i, j)))
+                    .map(|(i, j)| features(i, j).depth)
+                    .fold(1e-12, f64::max);
+                pass(&|i, j| {
+                    let depth = features(i, j).depth;
+                    if depth > 0. {
+                        Color3::ones() * (1. - depth / max_depth)
+                    } else {
+                        Color3::zero()
+                    }
+                })
+            }
+            Aov::Normal => pass(&|i, j| (features(i, j).normal + Vec3::ones()) / 2.),
+            Aov::Uv => pass(&|i, j| Color3::new(features(i, j).u, features(i, j).v, 0.)),
+            Aov::MaterialId => pass(&|i, j| id_color(features(i, j).material_id)),
+            Aov::ObjectId => pass(&|i, j| id_color(features(i, j).object_id)),
+            Aov::Emission => pass(&|i, j| film.radiance(i, j).emitted),
+            Aov::Direct => pass(&|i, j| film.radiance(i, j).direct),
+            Aov::Indirect => pass(&|i, j| film.radiance(i, j).indirect),
+            Aov::SampleCount => {
+                let max_count = (0..width)
+                    .flat_map(|i| (0..height).map(move |j| (i, j)))
+                    .map(|(i, j)| film.sample_count(i, j))
+                    .max()
+                    .unwrap_or(0)
+                    .max(1);
+                pass(&|i, j| Color3::ones() * film.sample_count(i, j) as f64 / max_count as f64)
+            }
+        }
+    }
+}
+
+/// A random but stable color for an ID, black for 0.
+fn id_color(id: usize) -> Color3 {
+    if id == 0 {
+        return Color3::zero();
+    }
+    let hash = mix_bits(id as u64);
+    let channel = |shift: u64| ((hash >> shift) & 0xff) as f64 / 255.;
+    Color3::new(channel(0), channel(8), channel(16))
+}
+
+#[test]
+fn test_id_color() {
+    assert_eq!(id_color(0), Color3::zero());
+    assert_eq!(id_color(7), id_color(7));
+    assert!(id_color(1) != id_color(2));
+}
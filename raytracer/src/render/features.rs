@@ -0,0 +1,115 @@
+use std::ops::{Add, AddAssign, Div};
+
+use super::scene::Scene;
+use crate::{
+    hittable::HitRecord, material::ScatterRecord, ray::Ray, rtweekend::clamp, Color3, Vec3,
+};
+
+fn saturate(c: Color3) -> Color3 {
+    Color3::new(
+        clamp(c.x(), 0., 1.),
+        clamp(c.y(), 0., 1.),
+        clamp(c.z(), 0., 1.),
+    )
+}
+
+fn first_id(a: usize, b: usize) -> usize {
+    if a != 0 {
+        a
+    } else {
+        b
+    }
+}
+
+/// First-hit surface properties, which guide the denoiser and make up most of
+/// the render passes. IDs can't be averaged, so sums keep the first nonzero one.
+#[derive(Clone, Copy)]
+pub struct Features {
+    pub albedo: Color3,
+    pub normal: Vec3,
+    pub depth: f64, // distance along the camera ray, 0 on a miss
+    pub u: f64,
+    pub v: f64,
+    pub material_id: usize, // 0 on a miss
+    pub object_id: usize,
+}
+
+impl Features {
+    pub fn zero() -> Self {
+        Self {
+            albedo: Color3::zero(),
+            normal: Vec3::zero(),
+            depth: 0.,
+            u: 0.,
+            v: 0.,
+            material_id: 0,
+            object_id: 0,
+        }
+    }
+
+    /// Gathers the features seen by a camera ray. Emitters report their
+    /// (clamped) radiance as albedo, and misses report the background.
+    pub fn from_ray(r: &Ray, scene: &Scene) -> Self {
+        let mut rec = HitRecord::new();
+        if !scene.world.hit(r, 0.000001, f64::INFINITY, &mut rec) {
+            return Self {
+                albedo: saturate(scene.background),
+                ..Self::zero()
+            };
+        }
+
+        let mat = rec.mat_ptr.clone().unwrap();
+        let mut srec = ScatterRecord::new();
+        let albedo = if mat.scatter(r, &rec, &mut srec) {
+            srec.attenuation
+        } else {
+            saturate(mat.emitted(r, &rec, rec.u, rec.v, &rec.p))
+        };
+        Self {
+            albedo,
+            normal: rec.normal,
+            depth: rec.t * r.direction().length(),
+            u: rec.u,
+            v: rec.v,
+            material_id: mat.id(),
+            object_id: rec.object_id,
+        }
+    }
+}
+
+impl Add for Features {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            albedo: self.albedo + other.albedo,
+            normal: self.normal + other.normal,
+            depth: self.depth + other.depth,
+            u: self.u + other.u,
+            v: self.v + other.v,
+            material_id: first_id(self.material_id, other.material_id),
+            object_id: first_id(self.object_id, other.object_id),
+        }
+    }
+}
+
+impl AddAssign for Features {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Div<f64> for Features {
+    type Output = Self;
+
+    fn div(self, other: f64) -> Self {
+        Self {
+            albedo: self.albedo / other,
+            normal: self.normal / other,
+            depth: self.depth / other,
+            u: self.u / other,
+            v: self.v / other,
+            ..self
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use crate::Color3;
+
+use super::{features::Features, filter::Filter, Radiance};
+
+/// Accumulates filter-weighted samples. Pixels are indexed as `[x][y]` with `y`
+/// pointing up, and pixel (x, y) covers [x, x + 1) x [y, y + 1) in film space.
+#[derive(Clone)]
+pub struct Film {
+    width: usize,
+    height: usize,
+    filter: Arc<dyn Filter + Send + Sync>,
+    sum: Vec<Vec<Radiance>>,
+    weight: Vec<Vec<f64>>,
+    sample_count: Vec<Vec<usize>>,
+    splat: Vec<Vec<Radiance>>, // light paths that connected straight to the camera
+    total_samples: usize,
+    features: Vec<Vec<Features>>,
+    invalid_sample_count: usize,
+    invalid_samples: Vec<(usize, usize)>, // where the first few were taken
+}
+
+const MAX_INVALID_REPORTED: usize = 8;
+
+impl Film {
+    pub fn new(width: usize, height: usize, filter: Arc<dyn Filter + Send + Sync>) -> Self {
+        Self {
+            width,
+            height,
+            filter,
+            sum: vec![vec![Radiance::zero(); height]; width],
+            weight: vec![vec![0.; height]; width],
+            sample_count: vec![vec![0; height]; width],
+            splat: vec![vec![Radiance::zero(); height]; width],
+            total_samples: 0,
+            features: vec![vec![Features::zero(); height]; width],
+            invalid_sample_count: 0,
+            invalid_samples: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Splats a sample taken at film position (`x`, `y`) onto every pixel
+    /// whose center lies within the filter radius.
+    pub fn add_sample(&mut self, x: f64, y: f64, radiance: Radiance) {
+        let radius = self.filter.radius();
+        let x0 = (x - 0.5 - radius).ceil().max(0.) as usize;
+        let x1 = ((x - 0.5 + radius).floor() as isize).min(self.width as isize - 1);
+        let y0 = (y - 0.5 - radius).ceil().max(0.) as usize;
+        let y1 = ((y - 0.5 + radius).floor() as isize).min(self.height as isize - 1);
+        if x1 < 0 || y1 < 0 {
+            return;
+        }
+
+        for i in x0..=x1 as usize {
+            for j in y0..=y1 as usize {
+                let weight = self.filter.evaluate(i as f64 + 0.5 - x, j as f64 + 0.5 - y);
+                self.sum[i][j] += radiance * weight;
+                self.weight[i][j] += weight;
+            }
+        }
+    }
+
+    /// Adds a light path contribution at film position (`x`, `y`), shared
+    /// between the pixels in reach by their filter weights. Every camera
+    /// sample may splat anywhere, so splats are scaled by the samples per pixel.
+    pub fn add_splat(&mut self, x: f64, y: f64, radiance: Radiance) {
+        if x < 0. || y < 0. || x >= self.width as f64 || y >= self.height as f64 {
+            return;
+        }
+        let radius = self.filter.radius();
+        let (x0, x1) = ((x - 0.5 - radius).ceil(), (x - 0.5 + radius).floor());
+        let (y0, y1) = ((y - 0.5 - radius).ceil(), (y - 0.5 + radius).floor());
+        // normalized over pixels off the film too, so the edges aren't brightened
+        let mut total = 0.;
+        let mut weights = Vec::new();
+        for i in x0 as isize..=x1 as isize {
+            for j in y0 as isize..=y1 as isize {
+                let weight = self.filter.evaluate(i as f64 + 0.5 - x, j as f64 + 0.5 - y);
+                total += weight;
+                if i >= 0 && j >= 0 && i < self.width as isize && j < self.height as isize {
+                    weights.push((i as usize, j as usize, weight));
+                }
+            }
+        }
+        if total.abs() < 1e-12 {
+            self.splat[x as usize][y as usize] += radiance;
+            return;
+        }
+        for (i, j, weight) in weights {
+            self.splat[i][j] += radiance * (weight / total);
+        }
+    }
+
+    /// Records how many samples were taken inside pixel (`i`, `j`).
+    pub fn add_sample_count(&mut self, i: usize, j: usize, count: usize) {
+        self.sample_count[i][j] += count;
+        self.total_samples += count;
+    }
+
+    /// Accumulates the features of one sample inside pixel (`i`, `j`). They are
+    /// box filtered, as blurring them across edges would defeat their purpose.
+    pub fn add_features(&mut self, i: usize, j: usize, features: Features) {
+        self.features[i][j] += features;
+    }
+
+    /// Records a NaN or infinite sample taken inside pixel (`i`, `j`).
+    pub fn add_invalid_sample(&mut self, i: usize, j: usize) {
+        self.invalid_sample_count += 1;
+        if self.invalid_samples.len() < MAX_INVALID_REPORTED {
+            self.invalid_samples.push((i, j));
+        }
+    }
+
+    pub fn invalid_sample_count(&self) -> usize {
+        self.invalid_sample_count
+    }
+
+    /// Pixels of the first few invalid samples.
+    pub fn invalid_samples(&self) -> &[(usize, usize)] {
+        &self.invalid_samples
+    }
+
+    pub fn sample_count(&self, i: usize, j: usize) -> usize {
+        self.sample_count[i][j]
+    }
+
+    pub fn average_sample_count(&self) -> f64 {
+        self.total_samples as f64 / (self.width * self.height) as f64
+    }
+
+    pub fn merge(&mut self, other: &Film) {
+        for i in 0..self.width {
+            for j in 0..self.height {
+                self.sum[i][j] += other.sum[i][j];
+                self.weight[i][j] += other.weight[i][j];
+                self.sample_count[i][j] += other.sample_count[i][j];
+                self.splat[i][j] += other.splat[i][j];
+                self.features[i][j] += other.features[i][j];
+            }
+        }
+        self.total_samples += other.total_samples;
+        self.invalid_sample_count += other.invalid_sample_count;
+        for &(i, j) in &other.invalid_samples {
+            if self.invalid_samples.len() < MAX_INVALID_REPORTED {
+                self.invalid_samples.push((i, j));
+            }
+        }
+    }
+
+    /// The reconstructed value of a pixel.
+    pub fn pixel(&self, i: usize, j: usize) -> Color3 {
+        self.radiance(i, j).total()
+    }
+
+    /// The reconstructed value of a pixel, split by bounce count.
+    pub fn radiance(&self, i: usize, j: usize) -> Radiance {
+        let filtered = if self.weight[i][j].abs() < 1e-12 {
+            Radiance::zero()
+        } else {
+            self.sum[i][j] / self.weight[i][j]
+        };
+        if self.total_samples == 0 {
+            filtered
+        } else {
+            filtered + self.splat[i][j] / self.average_sample_count()
+        }
+    }
+
+    /// The average features of a pixel, all zero if none were recorded.
+    pub fn features(&self, i: usize, j: usize) -> Features {
+        if self.sample_count[i][j] == 0 {
+            Features::zero()
+        } else {
+            self.features[i][j] / self.sample_count[i][j] as f64
+        }
+    }
+}
+
+#[test]
+fn test_box_splat() {
+    use super::filter::BoxFilter;
+
+    let mut film = Film::new(2, 2, Arc::new(BoxFilter::new(0.5)));
+    film.add_sample(0.25, 0.75, Color3::ones().into());
+    film.add_sample(0.75, 0.25, Color3::zero().into());
+    film.add_sample(1.5, 1.5, Color3::ones().into());
+    assert_eq!(film.pixel(0, 0), Color3::ones() * 0.5);
+    assert_eq!(film.pixel(1, 1), Color3::ones());
+    assert_eq!(film.pixel(1, 0), Color3::zero());
+}
+
+#[test]
+fn test_filtered_splat() {
+    use super::filter::TentFilter;
+
+    let mut film = Film::new(3, 3, Arc::new(TentFilter::new(1.)));
+    film.add_sample_count(0, 0, 9);
+    // halfway between two pixel centers
+    film.add_splat(1., 1.5, (Color3::ones() * 2.).into());
+    assert_eq!(film.pixel(0, 1), Color3::ones());
+    assert_eq!(film.pixel(1, 1), Color3::ones());
+    assert_eq!(film.pixel(1, 0), Color3::zero());
+}
@@ -0,0 +1,168 @@
+use std::f64::consts::PI;
+
+/// Pixel reconstruction filter. Offsets are measured in pixels from the pixel center.
+pub trait Filter {
+    /// Half-width of the square the filter is non-zero on.
+    fn radius(&self) -> f64;
+    fn evaluate(&self, x: f64, y: f64) -> f64;
+}
+
+/// Plain averaging of the samples inside each pixel.
+pub struct BoxFilter {
+    radius: f64,
+}
+
+impl BoxFilter {
+    pub fn new(radius: f64) -> Self {
+        Self { radius }
+    }
+}
+
+impl Filter for BoxFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, _x: f64, _y: f64) -> f64 {
+        1.
+    }
+}
+
+pub struct TentFilter {
+    radius: f64,
+}
+
+impl TentFilter {
+    pub fn new(radius: f64) -> Self {
+        Self { radius }
+    }
+}
+
+impl Filter for TentFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        (self.radius - x.abs()).max(0.) * (self.radius - y.abs()).max(0.)
+    }
+}
+
+pub struct GaussianFilter {
+    radius: f64,
+    alpha: f64,
+    exp_r: f64,
+}
+
+impl GaussianFilter {
+    /// `alpha` is the falloff rate, larger values give a narrower filter.
+    pub fn new(radius: f64, alpha: f64) -> Self {
+        Self {
+            radius,
+            alpha,
+            exp_r: (-alpha * radius * radius).exp(),
+        }
+    }
+
+    fn gaussian(&self, d: f64) -> f64 {
+        // shifted down so that it reaches zero at the radius
+        ((-self.alpha * d * d).exp() - self.exp_r).max(0.)
+    }
+}
+
+impl Filter for GaussianFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.gaussian(x) * self.gaussian(y)
+    }
+}
+
+/// Mitchell–Netravali cubic, `b = c = 1/3` is the recommended setting.
+pub struct MitchellFilter {
+    radius: f64,
+    b: f64,
+    c: f64,
+}
+
+impl MitchellFilter {
+    pub fn new(radius: f64, b: f64, c: f64) -> Self {
+        Self { radius, b, c }
+    }
+
+    fn mitchell_1d(&self, x: f64) -> f64 {
+        let x = (2. * x / self.radius).abs();
+        let (b, c) = (self.b, self.c);
+        if x > 2. {
+            0.
+        } else if x > 1. {
+            ((-b - 6. * c) * x * x * x
+                + (6. * b + 30. * c) * x * x
+                + (-12. * b - 48. * c) * x
+                + (8. * b + 24. * c))
+                / 6.
+        } else {
+            ((12. - 9. * b - 6. * c) * x * x * x
+                + (-18. + 12. * b + 6. * c) * x * x
+                + (6. - 2. * b))
+                / 6.
+        }
+    }
+}
+
+impl Filter for MitchellFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.mitchell_1d(x) * self.mitchell_1d(y)
+    }
+}
+
+pub struct BlackmanHarrisFilter {
+    radius: f64,
+}
+
+impl BlackmanHarrisFilter {
+    pub fn new(radius: f64) -> Self {
+        Self { radius }
+    }
+
+    fn window(&self, x: f64) -> f64 {
+        if x.abs() > self.radius {
+            return 0.;
+        }
+        // the window spans [-radius, radius]
+        let t = 2. * PI * (x / (2. * self.radius) + 0.5);
+        0.35875 - 0.48829 * t.cos() + 0.14128 * (2. * t).cos() - 0.01168 * (3. * t).cos()
+    }
+}
+
+impl Filter for BlackmanHarrisFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.window(x) * self.window(y)
+    }
+}
+
+#[test]
+fn test_filters_peak_at_center() {
+    let filters: Vec<Box<dyn Filter>> = vec![
+        Box::new(TentFilter::new(1.)),
+        Box::new(GaussianFilter::new(1.5, 2.)),
+        Box::new(MitchellFilter::new(2., 1. / 3., 1. / 3.)),
+        Box::new(BlackmanHarrisFilter::new(1.5)),
+    ];
+    for filter in filters {
+        let center = filter.evaluate(0., 0.);
+        assert!(center > 0.);
+        assert!(filter.evaluate(0.3, 0.2) < center);
+        assert!(filter.evaluate(filter.radius(), 0.).abs() < 1e-4);
+    }
+}
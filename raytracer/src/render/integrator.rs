@@ -0,0 +1,105 @@
+pub mod ambient_occlusion;
+pub mod bdpt;
+pub mod debug;
+pub mod direct;
+pub mod path;
+pub mod photon;
+
+use super::{scene::Scene, Radiance};
+use crate::{
+    camera::Camera,
+    hittable::{HitRecord, Hittable},
+    ray::Ray,
+    Color3, Point3, Vec3,
+};
+
+/// Light that reaches the camera at film position (`s`, `t`) in [0, 1]² rather
+/// than through the pixel being sampled.
+pub struct Splat {
+    pub s: f64,
+    pub t: f64,
+    pub radiance: Radiance,
+}
+
+impl Splat {
+    /// The pixel the splat lands in on a `width` by `height` film.
+    pub fn pixel(&self, width: usize, height: usize) -> (usize, usize) {
+        (
+            ((self.s * width as f64) as usize).min(width - 1),
+            ((self.t * height as f64) as usize).min(height - 1),
+        )
+    }
+}
+
+/// Estimates the radiance arriving along camera rays.
+pub trait Integrator {
+    fn li(&self, r: Ray, scene: &Scene, camera: &dyn Camera, splats: &mut Vec<Splat>) -> Radiance;
+}
+
+/// Direct lighting from the delta lights, which paths can never hit by chance.
+pub fn delta_lighting(r: &Ray, rec: &HitRecord, attenuation: Color3, scene: &Scene) -> Color3 {
+    let mut direct = Color3::zero();
+    for light in &scene.lights.delta {
+        let ls = light.sample_li(&rec.p);
+        if ls.radiance == Color3::zero() {
+            continue;
+        }
+        let shadow_ray = Ray::new(rec.p, ls.wi, r.time());
+        let mut shadow_rec = HitRecord::new();
+        if scene
+            .world
+            .hit(&shadow_ray, 0.001, ls.dist - 0.001, &mut shadow_rec)
+        {
+            continue;
+        }
+        let mat = rec.mat_ptr.clone().unwrap();
+        direct += Vec3::elemul(
+            mat.scattering(r, rec, attenuation, &shadow_ray),
+            ls.radiance,
+        );
+    }
+    direct
+}
+
+/// Direct lighting from one point sampled on the area lights.
+pub fn area_lighting(r: &Ray, rec: &HitRecord, attenuation: Color3, scene: &Scene) -> Color3 {
+    let sample = match scene.lights.area.sample_surface() {
+        Some(sample) => sample,
+        None => return Color3::zero(),
+    };
+    let w = sample.p - rec.p;
+    let le = emission(scene, sample.p, -w, r.time());
+    if le == Color3::zero() || !visible(scene, rec.p, sample.p, r.time()) {
+        return Color3::zero();
+    }
+    let scattered = Ray::new(rec.p, w, r.time());
+    let cosine = (sample.normal * w.unit()).abs();
+    let mat = rec.mat_ptr.clone().unwrap();
+    Vec3::elemul(mat.scattering(r, rec, attenuation, &scattered), le) * cosine
+        / (w.squared_length() * sample.pdf)
+}
+
+/// The radiance leaving light point `p` in direction `w`. The light list only
+/// holds stand-ins, so the actual emitter is found by looking back at `p`.
+pub fn emission(scene: &Scene, p: Point3, w: Vec3, time: f64) -> Color3 {
+    let w = w.unit();
+    let r = Ray::new(p + 0.001 * w, -w, time);
+    let mut rec = HitRecord::new();
+    if !scene.world.hit(&r, 0., 0.002, &mut rec) {
+        return Color3::zero();
+    }
+    rec.mat_ptr
+        .clone()
+        .unwrap()
+        .emitted(&r, &rec, rec.u, rec.v, &rec.p)
+}
+
+/// Whether nothing in the world lies between `a` and `b`.
+pub fn visible(scene: &Scene, a: Point3, b: Point3, time: f64) -> bool {
+    let d = b - a;
+    let eps = 0.001 / d.length();
+    let mut rec = HitRecord::new();
+    !scene
+        .world
+        .hit(&Ray::new(a, d, time), eps, 1. - eps, &mut rec)
+}
@@ -0,0 +1,52 @@
+use super::{Integrator, Splat};
+use crate::{
+    camera::Camera,
+    hittable::HitRecord,
+    pdf::{onb::Onb, random_cosine_direction},
+    ray::Ray,
+    render::{scene::Scene, Radiance},
+    Color3,
+};
+
+/// Ambient occlusion: the share of the cosine weighted hemisphere above the
+/// first hit that is clear of geometry for `radius`. Misses count as clear.
+pub struct AmbientOcclusionIntegrator {
+    radius: f64,
+    samples: usize, // rays per camera ray
+}
+
+impl AmbientOcclusionIntegrator {
+    pub fn new(radius: f64, samples: usize) -> Self {
+        Self { radius, samples }
+    }
+}
+
+impl Integrator for AmbientOcclusionIntegrator {
+    fn li(
+        &self,
+        r: Ray,
+        scene: &Scene,
+        _camera: &dyn Camera,
+        _splats: &mut Vec<Splat>,
+    ) -> Radiance {
+        let mut rec = HitRecord::new();
+        if !scene.world.hit(&r, 0.000001, f64::INFINITY, &mut rec) {
+            return Color3::ones().into();
+        }
+
+        let uvw = Onb::build_from_w(rec.normal);
+        let mut clear = 0;
+        for _ in 0..self.samples {
+            let occlusion_ray =
+                Ray::new(rec.p, uvw.local_vec(&random_cosine_direction()), r.time());
+            let mut occlusion_rec = HitRecord::new();
+            if !scene
+                .world
+                .hit(&occlusion_ray, 0.001, self.radius, &mut occlusion_rec)
+            {
+                clear += 1;
+            }
+        }
+        (Color3::ones() * clear as f64 / self.samples as f64).into()
+    }
+}
@@ -0,0 +1,541 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::{delta_lighting, emission, visible, Integrator, Splat};
+use crate::{
+    camera::Camera,
+    hittable::{HitRecord, Hittable},
+    material::ScatterRecord,
+    pdf::{onb::Onb, random_cosine_direction, Pdf},
+    ray::Ray,
+    render::{scene::Scene, Radiance},
+    rtweekend::random_double_unit,
+    Color3, Point3, Vec3,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum VertexKind {
+    Camera,
+    Light,
+    Surface,
+}
+
+/// A vertex of a camera or light subpath. Densities are per unit area, so that
+/// the two directions of travel can be compared for MIS.
+#[derive(Clone)]
+struct Vertex {
+    kind: VertexKind,
+    p: Point3,
+    normal: Vec3, // zero at the camera
+    beta: Color3, // throughput of the subpath up to here
+    delta: bool,  // scattered specularly, so it can't be connected to
+    pdf_fwd: f64,
+    pdf_rev: f64,
+    // Surfaces only
+    rec: Option<HitRecord>,
+    r_in: Ray,
+    attenuation: Color3,
+    pdf: Option<Arc<dyn Pdf + Send + Sync>>, // `None` if it didn't scatter
+    le: Color3,                              // emitted back along `r_in`
+}
+
+impl Vertex {
+    fn new(kind: VertexKind, p: Point3, normal: Vec3, beta: Color3, pdf_fwd: f64) -> Self {
+        Self {
+            kind,
+            p,
+            normal,
+            beta,
+            delta: false,
+            pdf_fwd,
+            pdf_rev: 0.,
+            rec: None,
+            r_in: Ray::new(p, Vec3::zero(), 0.),
+            attenuation: Color3::zero(),
+            pdf: None,
+            le: Color3::zero(),
+        }
+    }
+
+    fn is_connectible(&self) -> bool {
+        self.kind == VertexKind::Surface && !self.delta && self.pdf.is_some()
+    }
+
+    /// The BRDF times the cosine towards `next`, `scattering` in the terms
+    /// of `Material`.
+    fn f(&self, next: &Point3) -> Color3 {
+        let rec = self.rec.as_ref().unwrap();
+        let scattered = Ray::new(self.p, *next - self.p, self.r_in.time());
+        rec.mat_ptr
+            .clone()
+            .unwrap()
+            .scattering(&self.r_in, rec, self.attenuation, &scattered)
+    }
+
+    /// The density of a subpath going on from here to `next`, per unit area at
+    /// `next`, having come from `prev`, or along `r_in` if that is `None`.
+    /// Some materials, like `Hair`, sample depending on where the path came
+    /// from, so the material is asked rather than the pdf it sampled with.
+    fn pdf_area(&self, camera: &dyn Camera, prev: Option<&Point3>, next: &Vertex) -> f64 {
+        let w = next.p - self.p;
+        let pdf_dir = match self.kind {
+            VertexKind::Camera => camera.project(&next.p).map_or(0., |p| p.pdf_dir),
+            VertexKind::Light => emission_pdf(self.normal, w),
+            VertexKind::Surface if self.pdf.is_some() => {
+                let rec = self.rec.as_ref().unwrap();
+                let time = self.r_in.time();
+                let ray_in = match prev {
+                    Some(prev) => Ray::new(*prev, self.p - *prev, time),
+                    None => self.r_in,
+                };
+                rec.mat_ptr.clone().unwrap().scattering_pdf(
+                    &ray_in,
+                    rec,
+                    &Ray::new(self.p, w, time),
+                )
+            }
+            VertexKind::Surface => 0.,
+        };
+        convert_density(pdf_dir, w, next)
+    }
+}
+
+/// Light subpaths leave either side of the light with equal probability, then
+/// cosine weighted, as one-sided emitters can't be told apart from here.
+fn emission_pdf(normal: Vec3, w: Vec3) -> f64 {
+    (normal * w.unit()).abs() / (2. * PI)
+}
+
+/// Turns the solid angle density of direction `w` into an area density at `next`.
+fn convert_density(pdf_dir: f64, w: Vec3, next: &Vertex) -> f64 {
+    let cosine = if next.kind == VertexKind::Camera {
+        1.
+    } else {
+        (next.normal * w.unit()).abs()
+    };
+    pdf_dir * cosine / w.squared_length()
+}
+
+/// Extends a subpath along `r` until it escapes, is absorbed or holds
+/// `max_vertices`. Returns the throughput of a subpath that escaped.
+fn random_walk(
+    scene: &Scene,
+    mut r: Ray,
+    mut beta: Color3,
+    mut pdf_dir: f64,
+    max_vertices: usize,
+    path: &mut Vec<Vertex>,
+) -> Option<Color3> {
+    while path.len() < max_vertices {
+        let mut rec = HitRecord::new();
+        if !scene.world.hit(&r, 0.000001, f64::INFINITY, &mut rec) {
+            return Some(beta);
+        }
+
+        let mat = rec.mat_ptr.clone().unwrap();
+        let prev_p = path.last().unwrap().p;
+        let mut vertex = Vertex::new(VertexKind::Surface, rec.p, rec.normal, beta, 0.);
+        vertex.pdf_fwd = convert_density(pdf_dir, rec.p - prev_p, &vertex);
+        vertex.le = mat.emitted(&r, &rec, rec.u, rec.v, &rec.p);
+        vertex.r_in = r;
+
+        let mut srec = ScatterRecord::new();
+        if !mat.scatter(&r, &rec, &mut srec) {
+            vertex.rec = Some(rec);
+            path.push(vertex);
+            break;
+        }
+        vertex.attenuation = srec.attenuation;
+
+        let (next, pdf_rev_dir) = if srec.is_specular {
+            vertex.delta = true;
+            beta = Vec3::elemul(beta, srec.attenuation);
+            pdf_dir = 0.;
+            (srec.specular_ray, 0.)
+        } else {
+            let pdf = srec.pdf_ptr.unwrap();
+            let next = Ray::new(rec.p, pdf.generate(), r.time());
+            pdf_dir = pdf.value(&next.direction());
+            if pdf_dir <= 0. {
+                vertex.rec = Some(rec);
+                path.push(vertex);
+                break;
+            }
+            beta = Vec3::elemul(
+                beta,
+                mat.scattering(&r, &rec, srec.attenuation, &next) / pdf_dir,
+            );
+            // the density of coming back the other way, in along `next`
+            let pdf_rev_dir = mat.scattering_pdf(
+                &Ray::new(next.at(1.), -next.direction(), r.time()),
+                &rec,
+                &Ray::new(rec.p, -r.direction(), r.time()),
+            );
+            vertex.pdf = Some(pdf);
+            (next, pdf_rev_dir)
+        };
+
+        let prev = path.last().unwrap();
+        let prev_pdf_rev = convert_density(pdf_rev_dir, prev_p - rec.p, prev);
+        path.last_mut().unwrap().pdf_rev = prev_pdf_rev;
+        vertex.rec = Some(rec);
+        path.push(vertex);
+
+        if beta == Color3::zero() {
+            break;
+        }
+        r = next;
+    }
+    None
+}
+
+/// Bidirectional path tracing (Veach 1997), following the structure of pbrt.
+/// Every prefix of a camera subpath is connected to every prefix of a light
+/// subpath, and the strategies are combined with the balance heuristic.
+/// Light subpaths that connect to the camera are splatted onto the film, which
+/// needs a pinhole camera; other cameras just lose those strategies.
+pub struct BdptIntegrator {
+    max_depth: usize,
+}
+
+impl BdptIntegrator {
+    pub fn new(max_depth: usize) -> Self {
+        Self { max_depth }
+    }
+
+    fn light_subpath(&self, scene: &Scene, time: f64) -> Vec<Vertex> {
+        let mut path = Vec::new();
+        let sample = match scene.lights.area.sample_surface() {
+            Some(sample) => sample,
+            None => return path,
+        };
+
+        let side = if random_double_unit() < 0.5 {
+            sample.normal
+        } else {
+            -sample.normal
+        };
+        let w = Onb::build_from_w(side).local_vec(&random_cosine_direction());
+        let pdf_dir = emission_pdf(sample.normal, w);
+        let le = emission(scene, sample.p, w, time);
+        let mut light = Vertex::new(
+            VertexKind::Light,
+            sample.p,
+            sample.normal,
+            le / sample.pdf,
+            sample.pdf,
+        );
+        light.le = le;
+        path.push(light);
+        if le == Color3::zero() || pdf_dir <= 0. {
+            return path;
+        }
+
+        let beta = le * (sample.normal * w.unit()).abs() / (sample.pdf * pdf_dir);
+        random_walk(
+            scene,
+            Ray::new(sample.p, w, time),
+            beta,
+            pdf_dir,
+            self.max_depth + 1,
+            &mut path,
+        );
+        path
+    }
+
+    /// The contribution of the path made of `s` light and `t` camera vertices,
+    /// and where it lands on the film if it connects to the camera.
+    #[allow(clippy::too_many_arguments)]
+    fn connect(
+        &self,
+        scene: &Scene,
+        camera: &dyn Camera,
+        light_path: &[Vertex],
+        camera_path: &[Vertex],
+        s: usize,
+        t: usize,
+        time: f64,
+        connectable: bool,
+    ) -> Option<(Color3, Option<(f64, f64)>)> {
+        let mut sampled = None;
+        let mut film_position = None;
+
+        let l = if s == 0 {
+            // the camera subpath found a light by itself
+            let pt = &camera_path[t - 1];
+            Vec3::elemul(pt.beta, pt.le)
+        } else if t == 1 {
+            let qs = &light_path[s - 1];
+            if !qs.is_connectible() {
+                return None;
+            }
+            let projection = camera.project(&qs.p)?;
+            if !visible(scene, qs.p, projection.origin, time) {
+                return None;
+            }
+            let distance_squared = (projection.origin - qs.p).squared_length();
+            film_position = Some((projection.s, projection.t));
+            sampled = Some(Vertex::new(
+                VertexKind::Camera,
+                projection.origin,
+                Vec3::zero(),
+                Color3::ones(),
+                1.,
+            ));
+            Vec3::elemul(qs.beta, qs.f(&projection.origin)) * projection.pdf_dir / distance_squared
+        } else if s == 1 {
+            // next event estimation with a fresh point on a light
+            let pt = &camera_path[t - 1];
+            if !pt.is_connectible() {
+                return None;
+            }
+            let sample = scene.lights.area.sample_surface()?;
+            let w = sample.p - pt.p;
+            let le = emission(scene, sample.p, -w, time);
+            if le == Color3::zero() || !visible(scene, pt.p, sample.p, time) {
+                return None;
+            }
+            let cosine = (sample.normal * w.unit()).abs();
+            let mut light = Vertex::new(
+                VertexKind::Light,
+                sample.p,
+                sample.normal,
+                le / sample.pdf,
+                sample.pdf,
+            );
+            light.le = le;
+            sampled = Some(light);
+            Vec3::elemul(Vec3::elemul(pt.beta, pt.f(&sample.p)), le) * cosine
+                / (w.squared_length() * sample.pdf)
+        } else {
+            let qs = &light_path[s - 1];
+            let pt = &camera_path[t - 1];
+            if !qs.is_connectible() || !pt.is_connectible() {
+                return None;
+            }
+            let distance_squared = (pt.p - qs.p).squared_length();
+            let l = Vec3::elemul(
+                Vec3::elemul(qs.beta, qs.f(&pt.p)),
+                Vec3::elemul(pt.f(&qs.p), pt.beta),
+            ) / distance_squared;
+            if l == Color3::zero() || !visible(scene, qs.p, pt.p, time) {
+                return None;
+            }
+            l
+        };
+
+        if l == Color3::zero() {
+            return None;
+        }
+        let weight = mis_weight(
+            scene,
+            camera,
+            light_path,
+            camera_path,
+            sampled.as_ref(),
+            s,
+            t,
+            connectable,
+        );
+        Some((l * weight, film_position))
+    }
+}
+
+/// The balance heuristic weight of strategy (`s`, `t`), from the ratios of the
+/// densities of all the other strategies that could have made the same path.
+#[allow(clippy::too_many_arguments)]
+fn mis_weight(
+    scene: &Scene,
+    camera: &dyn Camera,
+    light_path: &[Vertex],
+    camera_path: &[Vertex],
+    sampled: Option<&Vertex>,
+    s: usize,
+    t: usize,
+    connectable: bool,
+) -> f64 {
+    if s + t == 2 {
+        return 1.;
+    }
+    let remap0 = |f: f64| if f != 0. { f } else { 1. };
+
+    // (pdf_fwd, pdf_rev, delta) of every vertex, as seen by this strategy
+    let mut cam: Vec<(f64, f64, bool)> = camera_path[..t]
+        .iter()
+        .map(|v| (v.pdf_fwd, v.pdf_rev, v.delta))
+        .collect();
+    let mut light: Vec<(f64, f64, bool)> = light_path[..s]
+        .iter()
+        .map(|v| (v.pdf_fwd, v.pdf_rev, v.delta))
+        .collect();
+
+    let qs = match s {
+        0 => None,
+        1 => sampled,
+        _ => Some(&light_path[s - 1]),
+    };
+    let pt = if t == 1 {
+        sampled.unwrap()
+    } else {
+        &camera_path[t - 1]
+    };
+    if s == 1 {
+        light[0].0 = qs.unwrap().pdf_fwd;
+    }
+    let pt_minus = if t >= 2 {
+        Some(&camera_path[t - 2])
+    } else {
+        None
+    };
+
+    cam[t - 1].1 = match qs {
+        Some(qs) => qs.pdf_area(camera, None, pt),
+        None => {
+            let pt_minus = pt_minus.unwrap();
+            scene
+                .lights
+                .area
+                .surface_pdf(&pt_minus.p, &(pt.p - pt_minus.p))
+        }
+    };
+    if s == 0 && cam[t - 1].1 == 0. {
+        // an emitter the lights don't know about, so this is the only strategy
+        return 1.;
+    }
+    cam[t - 1].2 = false;
+    if let Some(pt_minus) = pt_minus {
+        cam[t - 2].1 = match qs {
+            Some(qs) => pt.pdf_area(camera, Some(&qs.p), pt_minus),
+            None => {
+                let w = pt_minus.p - pt.p;
+                convert_density(emission_pdf(pt.normal, w), w, pt_minus)
+            }
+        };
+    }
+    if let Some(qs) = qs {
+        light[s - 1].1 = pt.pdf_area(camera, None, qs);
+        light[s - 1].2 = false;
+        if s >= 2 {
+            light[s - 2].1 = qs.pdf_area(camera, Some(&pt.p), &light_path[s - 2]);
+        }
+    }
+
+    let mut sum_ri = 0.;
+    let mut ri = 1.;
+    for i in (1..t).rev() {
+        ri *= remap0(cam[i].1) / remap0(cam[i].0);
+        // i == 1 stands for connecting to the camera
+        if !cam[i].2 && !cam[i - 1].2 && (i > 1 || connectable) {
+            sum_ri += ri;
+        }
+    }
+    ri = 1.;
+    for i in (0..s).rev() {
+        ri *= remap0(light[i].1) / remap0(light[i].0);
+        let delta_before = i > 0 && light[i - 1].2;
+        if !light[i].2 && !delta_before {
+            sum_ri += ri;
+        }
+    }
+    1. / (1. + sum_ri)
+}
+
+/// Non-specular bounces on the path of strategy (`s`, `t`), not counting the
+/// light and the camera.
+fn bounces(light_path: &[Vertex], camera_path: &[Vertex], s: usize, t: usize) -> usize {
+    let camera_end = if s == 0 { t - 1 } else { t };
+    let on_camera = camera_path[1..camera_end]
+        .iter()
+        .filter(|v| !v.delta)
+        .count();
+    let on_light = if s >= 2 {
+        light_path[1..s].iter().filter(|v| !v.delta).count()
+    } else {
+        0
+    };
+    on_camera + on_light
+}
+
+impl Integrator for BdptIntegrator {
+    fn li(&self, r: Ray, scene: &Scene, camera: &dyn Camera, splats: &mut Vec<Splat>) -> Radiance {
+        let mut radiance = Radiance::zero();
+        let time = r.time();
+
+        let projection = camera.project(&r.at(1.));
+        let connectable = projection.is_some();
+        let mut camera_path = vec![Vertex::new(
+            VertexKind::Camera,
+            r.origin(),
+            Vec3::zero(),
+            Color3::ones(),
+            1.,
+        )];
+        let escaped = random_walk(
+            scene,
+            r,
+            Color3::ones(),
+            projection.map_or(1., |p| p.pdf_dir),
+            self.max_depth + 2,
+            &mut camera_path,
+        );
+        if let Some(beta) = escaped {
+            // only camera subpaths can find the background
+            let n = camera_path.len();
+            radiance.accumulate(
+                bounces(&[], &camera_path, 0, n + 1),
+                Vec3::elemul(beta, scene.background),
+            );
+        }
+        let light_path = self.light_subpath(scene, time);
+
+        // Delta lights can't be reached by light subpaths here, so they get
+        // plain shadow rays, which is the only strategy that could find them
+        for t in 2..=camera_path.len() {
+            let pt = &camera_path[t - 1];
+            if !pt.is_connectible() {
+                continue;
+            }
+            let direct = delta_lighting(&pt.r_in, pt.rec.as_ref().unwrap(), pt.attenuation, scene);
+            radiance.accumulate(
+                bounces(&light_path, &camera_path, 1, t) + 1,
+                Vec3::elemul(pt.beta, direct),
+            );
+        }
+
+        for t in 1..=camera_path.len() {
+            for s in 0..=light_path.len() {
+                if (s == 1 && t == 1) || s + t < 2 || s + t - 2 > self.max_depth {
+                    continue;
+                }
+                if t == 1 && !connectable {
+                    continue;
+                }
+                if let Some((l, film_position)) = self.connect(
+                    scene,
+                    camera,
+                    &light_path,
+                    &camera_path,
+                    s,
+                    t,
+                    time,
+                    connectable,
+                ) {
+                    let bounces = bounces(&light_path, &camera_path, s, t);
+                    match film_position {
+                        Some((s, t)) => {
+                            let mut splat = Radiance::zero();
+                            splat.accumulate(bounces, l);
+                            splats.push(Splat {
+                                s,
+                                t,
+                                radiance: splat,
+                            });
+                        }
+                        None => radiance.accumulate(bounces, l),
+                    }
+                }
+            }
+        }
+
+        radiance
+    }
+}
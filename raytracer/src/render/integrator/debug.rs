@@ -0,0 +1,111 @@
+use super::{Integrator, Splat};
+use crate::{
+    camera::Camera,
+    hittable::{bvh, HitRecord},
+    ray::Ray,
+    render::{scene::Scene, Radiance},
+    Color3, Vec3,
+};
+
+/// What the debug integrator shows about the first hit.
+#[derive(Clone, Copy)]
+pub enum DebugView {
+    Normal, // outward facing
+    Uv,
+    BvhHeatMap { max_nodes: usize }, // nodes visited, blue to red at `max_nodes`
+}
+
+/// Shows geometry rather than light, at one ray per sample.
+pub struct DebugIntegrator {
+    view: DebugView,
+}
+
+impl DebugIntegrator {
+    pub fn new(view: DebugView) -> Self {
+        if let DebugView::BvhHeatMap { .. } = view {
+            bvh::start_counting_nodes();
+        }
+        Self { view }
+    }
+}
+
+impl Drop for DebugIntegrator {
+    fn drop(&mut self) {
+        if let DebugView::BvhHeatMap { .. } = self.view {
+            bvh::stop_counting_nodes();
+        }
+    }
+}
+
+/// Blue through green to red as `x` goes from 0 to 1.
+fn heat_color(x: f64) -> Color3 {
+    let x = x.clamp(0., 1.);
+    if x < 0.5 {
+        Color3::new(0., 2. * x, 1. - 2. * x)
+    } else {
+        Color3::new(2. * x - 1., 2. - 2. * x, 0.)
+    }
+}
+
+impl Integrator for DebugIntegrator {
+    fn li(
+        &self,
+        r: Ray,
+        scene: &Scene,
+        _camera: &dyn Camera,
+        _splats: &mut Vec<Splat>,
+    ) -> Radiance {
+        bvh::take_nodes_visited();
+        let mut rec = HitRecord::new();
+        let hit = scene.world.hit(&r, 0.000001, f64::INFINITY, &mut rec);
+
+        let value = match self.view {
+            DebugView::BvhHeatMap { max_nodes } => {
+                heat_color(bvh::take_nodes_visited() as f64 / max_nodes as f64)
+            }
+            _ if !hit => Color3::zero(),
+            DebugView::Normal => {
+                let outward = if rec.front_face {
+                    rec.normal
+                } else {
+                    -rec.normal
+                };
+                (outward + Vec3::ones()) / 2.
+            }
+            DebugView::Uv => Color3::new(rec.u, rec.v, 0.),
+        };
+        // squared, so that the gamma correction of the output gives it back
+        Vec3::elemul(value, value).into()
+    }
+}
+
+#[test]
+fn test_heat_map_counts_while_alive() {
+    use crate::{
+        hittable::{bvh::BVH, hittable_list::HittableList, sphere::Sphere, Hittable},
+        material::Lambertian,
+        Point3,
+    };
+    use std::sync::Arc;
+
+    let mut list = HittableList::new();
+    for x in [-2., 2.] {
+        list.add(Arc::new(Sphere::new(
+            Point3::new(x, 0., 0.),
+            1.,
+            Arc::new(Lambertian::new(Color3::ones())),
+        )));
+    }
+    let bvh = BVH::new(&list, 0., 0.);
+    let r = Ray::new(Point3::new(2., 0., 5.), Vec3::new(0., 0., -1.), 0.);
+    let trace = || {
+        bvh::take_nodes_visited();
+        bvh.hit(&r, 0.001, f64::INFINITY, &mut HitRecord::new());
+        bvh::take_nodes_visited()
+    };
+
+    let heat_map = DebugIntegrator::new(DebugView::BvhHeatMap { max_nodes: 64 });
+    assert!(trace() > 0);
+    drop(heat_map);
+    assert_eq!(trace(), 0);
+}
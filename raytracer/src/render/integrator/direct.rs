@@ -0,0 +1,64 @@
+use super::{area_lighting, delta_lighting, Integrator, Splat};
+use crate::{
+    camera::Camera,
+    hittable::HitRecord,
+    material::ScatterRecord,
+    ray::Ray,
+    render::{scene::Scene, Radiance},
+    Color3, Vec3,
+};
+
+/// Direct lighting only: emission and one light sample at the first diffuse
+/// surface, seen through up to `max_depth` specular bounces.
+pub struct DirectLightingIntegrator {
+    max_depth: usize,
+}
+
+impl DirectLightingIntegrator {
+    pub fn new(max_depth: usize) -> Self {
+        Self { max_depth }
+    }
+}
+
+impl Integrator for DirectLightingIntegrator {
+    fn li(
+        &self,
+        r: Ray,
+        scene: &Scene,
+        _camera: &dyn Camera,
+        _splats: &mut Vec<Splat>,
+    ) -> Radiance {
+        let mut radiance = Radiance::zero();
+        let mut throughput = Color3::ones();
+        let mut ray = r;
+
+        for _ in 0..self.max_depth {
+            let mut rec = HitRecord::new();
+            if !scene.world.hit(&ray, 0.000001, f64::INFINITY, &mut rec) {
+                radiance.accumulate(0, Vec3::elemul(throughput, scene.background));
+                break;
+            }
+
+            let mat = rec.mat_ptr.clone().unwrap();
+            let emitted = mat.emitted(&ray, &rec, rec.u, rec.v, &rec.p);
+            radiance.accumulate(0, Vec3::elemul(throughput, emitted));
+
+            let mut srec = ScatterRecord::new();
+            if !mat.scatter(&ray, &rec, &mut srec) {
+                break;
+            }
+            if srec.is_specular {
+                throughput = Vec3::elemul(throughput, srec.attenuation);
+                ray = srec.specular_ray;
+                continue;
+            }
+
+            let direct = delta_lighting(&ray, &rec, srec.attenuation, scene)
+                + area_lighting(&ray, &rec, srec.attenuation, scene);
+            radiance.accumulate(1, Vec3::elemul(throughput, direct));
+            break;
+        }
+
+        radiance
+    }
+}
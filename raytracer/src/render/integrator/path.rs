@@ -0,0 +1,219 @@
+use std::sync::Arc;
+
+use super::{delta_lighting, Integrator, Splat};
+use crate::{
+    camera::Camera,
+    hittable::HitRecord,
+    material::{Lobe, ScatterRecord},
+    pdf::{HittablePdf, MixturePdf, Pdf},
+    ray::Ray,
+    render::{scene::Scene, Radiance},
+    rtweekend::random_double_unit,
+    Color3, Vec3,
+};
+
+/// The most bounces of each kind a path may take.
+#[derive(Clone, Copy)]
+pub struct BounceLimits {
+    pub diffuse: usize,
+    pub specular: usize,
+    pub transmission: usize,
+    pub volume: usize,
+}
+
+impl BounceLimits {
+    pub fn new(diffuse: usize, specular: usize, transmission: usize, volume: usize) -> Self {
+        Self {
+            diffuse,
+            specular,
+            transmission,
+            volume,
+        }
+    }
+
+    fn get_mut(&mut self, lobe: Lobe) -> &mut usize {
+        match lobe {
+            Lobe::Diffuse => &mut self.diffuse,
+            Lobe::Specular => &mut self.specular,
+            Lobe::Transmission => &mut self.transmission,
+            Lobe::Volume => &mut self.volume,
+        }
+    }
+}
+
+/// Unidirectional path tracing with light sampling for the area lights and
+/// shadow rays for the delta lights.
+pub struct PathIntegrator {
+    max_depth: usize,
+    bounce_limits: BounceLimits,
+    // specular bounces after a diffuse one are blurred into a cone of this
+    // radius, trading biased caustics for far fewer fireflies
+    regularization: Option<f64>,
+    russian_roulette: Option<usize>, // bounces before paths may be terminated
+}
+
+impl PathIntegrator {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            max_depth,
+            bounce_limits: BounceLimits::new(max_depth, max_depth, max_depth, max_depth),
+            regularization: None,
+            russian_roulette: None,
+        }
+    }
+
+    pub fn with_bounce_limits(mut self, bounce_limits: BounceLimits) -> Self {
+        self.bounce_limits = bounce_limits;
+        self
+    }
+
+    pub fn with_regularization(mut self, radius: f64) -> Self {
+        self.regularization = Some(radius);
+        self
+    }
+
+    pub fn with_russian_roulette(mut self, min_bounces: usize) -> Self {
+        self.russian_roulette = Some(min_bounces);
+        self
+    }
+
+    /// Russian roulette: past `min_bounces`, a path survives with a probability
+    /// following its throughput, and survivors are boosted to stay unbiased.
+    /// Returns the survival probability, or `None` if the path was terminated.
+    fn roulette(&self, bounce: usize, throughput: Color3) -> Option<f64> {
+        match self.russian_roulette {
+            Some(min_bounces) if bounce >= min_bounces => {
+                let q = throughput
+                    .x()
+                    .max(throughput.y())
+                    .max(throughput.z())
+                    .min(0.95);
+                if random_double_unit() < q {
+                    Some(q)
+                } else {
+                    None
+                }
+            }
+            _ => Some(1.),
+        }
+    }
+}
+
+impl Integrator for PathIntegrator {
+    fn li(
+        &self,
+        r: Ray,
+        scene: &Scene,
+        _camera: &dyn Camera,
+        _splats: &mut Vec<Splat>,
+    ) -> Radiance {
+        let mut radiance = Radiance::zero();
+        let mut throughput = Color3::ones();
+        let mut ray = r;
+        let mut remaining = self.bounce_limits;
+        // specular bounces are seen through, so they don't count here
+        let mut diffuse_bounces = 0;
+
+        for depth in 0..self.max_depth {
+            let mut rec = HitRecord::new();
+            if !scene.world.hit(&ray, 0.000001, f64::INFINITY, &mut rec) {
+                radiance.accumulate(diffuse_bounces, Vec3::elemul(throughput, scene.background));
+                break;
+            }
+
+            let mat = rec.mat_ptr.clone().unwrap();
+            let emitted = mat.emitted(&ray, &rec, rec.u, rec.v, &rec.p);
+            radiance.accumulate(diffuse_bounces, Vec3::elemul(throughput, emitted));
+
+            let mut srec = ScatterRecord::new();
+            if !mat.scatter(&ray, &rec, &mut srec) {
+                break;
+            }
+            let remaining_of_lobe = remaining.get_mut(srec.lobe);
+            if *remaining_of_lobe == 0 {
+                break;
+            }
+            *remaining_of_lobe -= 1;
+
+            if srec.is_specular {
+                let mut specular_ray = srec.specular_ray;
+                if let (Some(radius), true) = (self.regularization, diffuse_bounces > 0) {
+                    let direction =
+                        specular_ray.direction().unit() + radius * Vec3::random_in_unit_sphere();
+                    specular_ray = Ray::new(specular_ray.origin(), direction, specular_ray.time());
+                }
+                throughput = Vec3::elemul(throughput, srec.attenuation);
+                ray = specular_ray;
+            } else {
+                let direct = delta_lighting(&ray, &rec, srec.attenuation, scene);
+                radiance.accumulate(diffuse_bounces + 1, Vec3::elemul(throughput, direct));
+
+                let surface_pdf = srec.pdf_ptr.unwrap();
+                let scatter_pdf: Arc<dyn Pdf + Send + Sync> =
+                    if scene.lights.area.objects.is_empty() {
+                        surface_pdf
+                    } else {
+                        let light_ptr =
+                            Arc::new(HittablePdf::new(scene.lights.area.clone(), &rec.p));
+                        Arc::new(MixturePdf::new(light_ptr, surface_pdf))
+                    };
+                let scattered = Ray::new(rec.p, scatter_pdf.generate(), ray.time());
+                let pdf_val = scatter_pdf.value(&scattered.direction());
+                if pdf_val <= 0. || !pdf_val.is_finite() {
+                    break;
+                }
+
+                let weight = mat.scattering(&ray, &rec, srec.attenuation, &scattered) / pdf_val;
+                throughput = Vec3::elemul(throughput, weight);
+                ray = scattered;
+                diffuse_bounces += 1;
+            }
+
+            match self.roulette(depth, throughput) {
+                Some(q) => throughput /= q,
+                None => break,
+            }
+        }
+
+        radiance
+    }
+}
+
+#[test]
+fn test_bounce_limits() {
+    use crate::{
+        hittable::{hittable_list::HittableList, sphere::Sphere},
+        light::LightList,
+        material::Metal,
+        Point3,
+    };
+
+    // A mirror ball under a white sky: without specular bounces it is black
+    let mut objects = HittableList::new();
+    objects.add(Arc::new(Sphere::new(
+        Point3::new(0., 0., -2.),
+        0.5,
+        Arc::new(Metal::new(Color3::ones() * 0.5, 0.)),
+    )));
+    let scene = Scene::new(
+        Arc::new(objects),
+        LightList::new(HittableList::new(), Vec::new()),
+        Color3::ones(),
+    );
+    let r = Ray::new(Point3::zero(), Vec3::new(0., 0., -1.), 0.);
+
+    let matte = PathIntegrator::new(50).with_bounce_limits(BounceLimits::new(50, 0, 50, 50));
+    let mirror = PathIntegrator::new(50);
+    let cam = crate::camera::orthographic::OrthographicCamera::new(
+        Point3::zero(),
+        Point3::new(0., 0., -1.),
+        Vec3::new(0., 1., 0.),
+        1.,
+        1.,
+        0.,
+        1.,
+    );
+    let li = |integrator: &PathIntegrator| integrator.li(r, &scene, &cam, &mut Vec::new());
+    assert_eq!(li(&matte).total(), Color3::zero());
+    assert_eq!(li(&mirror).total(), Color3::ones() * 0.5);
+}
@@ -0,0 +1,341 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::{area_lighting, delta_lighting, emission, Integrator, Splat};
+use crate::{
+    camera::Camera,
+    hittable::{HitRecord, Hittable},
+    material::ScatterRecord,
+    pdf::{onb::Onb, random_cosine_direction},
+    ray::Ray,
+    render::{film::Film, render, scene::Scene, Radiance, RenderSettings},
+    rtweekend::{random_double, random_double_unit},
+    Color3, Point3, Vec3,
+};
+
+#[derive(Clone, Copy)]
+struct Photon {
+    p: Point3,
+    normal: Vec3, // of the surface, on the side the photon arrived from
+    wi: Vec3,     // towards where the photon came from
+    power: Color3,
+}
+
+/// Photons in a balanced kd-tree, stored implicitly: the median of every
+/// range is its node, splitting the range along `axes` of that node.
+pub struct PhotonMap {
+    photons: Vec<Photon>,
+    axes: Vec<usize>,
+}
+
+impl PhotonMap {
+    fn new(mut photons: Vec<Photon>) -> Self {
+        let mut axes = vec![0; photons.len()];
+        Self::build(&mut photons, &mut axes);
+        Self { photons, axes }
+    }
+
+    fn build(photons: &mut [Photon], axes: &mut [usize]) {
+        if photons.is_empty() {
+            return;
+        }
+        let mut min = photons[0].p;
+        let mut max = photons[0].p;
+        for photon in photons.iter() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(photon.p[axis]);
+                max[axis] = max[axis].max(photon.p[axis]);
+            }
+        }
+        let extent = max - min;
+        let axis = if extent.x() > extent.y() && extent.x() > extent.z() {
+            0
+        } else if extent.y() > extent.z() {
+            1
+        } else {
+            2
+        };
+
+        let mid = photons.len() / 2;
+        photons.select_nth_unstable_by(mid, |a, b| a.p[axis].partial_cmp(&b.p[axis]).unwrap());
+        axes[mid] = axis;
+        let (left, right) = photons.split_at_mut(mid);
+        let (left_axes, right_axes) = axes.split_at_mut(mid);
+        Self::build(left, left_axes);
+        Self::build(&mut right[1..], &mut right_axes[1..]);
+    }
+
+    /// Emits `count` photons from the area lights at times spread over the
+    /// shutter interval `(time0, time1)` and records where they land on
+    /// diffuse surfaces after at least one bounce. Photons that come straight
+    /// from a light are left out, as direct lighting is sampled.
+    pub fn trace(scene: &Scene, count: usize, max_depth: usize, shutter: (f64, f64)) -> Self {
+        let mut photons = Vec::new();
+        for _ in 0..count {
+            let sample = match scene.lights.area.sample_surface() {
+                Some(sample) => sample,
+                None => break,
+            };
+            let side = if random_double_unit() < 0.5 {
+                sample.normal
+            } else {
+                -sample.normal
+            };
+            let w = Onb::build_from_w(side).local_vec(&random_cosine_direction());
+            let time = random_double(shutter.0, shutter.1);
+            let le = emission(scene, sample.p, w, time);
+            if le == Color3::zero() {
+                continue;
+            }
+            // pdf of the direction is |cos| / 2π
+            let mut power = le * 2. * PI / (sample.pdf * count as f64);
+            let mut r = Ray::new(sample.p, w, time);
+
+            for depth in 0..max_depth {
+                let mut rec = HitRecord::new();
+                if !scene.world.hit(&r, 0.000001, f64::INFINITY, &mut rec) {
+                    break;
+                }
+                let mat = rec.mat_ptr.clone().unwrap();
+                let mut srec = ScatterRecord::new();
+                if !mat.scatter(&r, &rec, &mut srec) {
+                    break;
+                }
+                if srec.is_specular {
+                    power = Vec3::elemul(power, srec.attenuation);
+                    r = srec.specular_ray;
+                    continue;
+                }
+
+                if depth > 0 {
+                    photons.push(Photon {
+                        p: rec.p,
+                        normal: rec.normal,
+                        wi: -r.direction().unit(),
+                        power,
+                    });
+                }
+
+                let pdf = srec.pdf_ptr.unwrap();
+                let scattered = Ray::new(rec.p, pdf.generate(), r.time());
+                let pdf_val = pdf.value(&scattered.direction());
+                if pdf_val <= 0. {
+                    break;
+                }
+                let weight = mat.scattering(&r, &rec, srec.attenuation, &scattered) / pdf_val;
+                // keep the power of survivors about the same
+                let q = weight.x().max(weight.y()).max(weight.z()).min(0.95);
+                if random_double_unit() >= q {
+                    break;
+                }
+                power = Vec3::elemul(power, weight / q);
+                r = scattered;
+            }
+        }
+        Self::new(photons)
+    }
+
+    fn for_each_within(&self, p: &Point3, radius: f64, f: &mut dyn FnMut(&Photon)) {
+        self.search(0, self.photons.len(), p, radius, f);
+    }
+
+    fn search(
+        &self,
+        begin: usize,
+        end: usize,
+        p: &Point3,
+        radius: f64,
+        f: &mut dyn FnMut(&Photon),
+    ) {
+        if begin >= end {
+            return;
+        }
+        let mid = begin + (end - begin) / 2;
+        let photon = &self.photons[mid];
+        if (photon.p - *p).squared_length() <= radius * radius {
+            f(photon);
+        }
+        let axis = self.axes[mid];
+        let d = p[axis] - photon.p[axis];
+        if d <= radius {
+            self.search(begin, mid, p, radius, f);
+        }
+        if d >= -radius {
+            self.search(mid + 1, end, p, radius, f);
+        }
+    }
+}
+
+/// Photon mapping (Jensen 1996). Camera paths are followed through specular
+/// bounces; at the first diffuse one, direct lighting is sampled and the rest,
+/// caustics included, is estimated from the photons within `radius`.
+/// Only area lights emit photons, delta lights just light directly.
+pub struct PhotonMapIntegrator {
+    photon_map: PhotonMap,
+    radius: f64,
+    max_depth: usize,
+}
+
+impl PhotonMapIntegrator {
+    /// Traces the photon map for one frame, whose camera is open during
+    /// `shutter`.
+    pub fn new(
+        scene: &Scene,
+        photon_count: usize,
+        radius: f64,
+        max_depth: usize,
+        shutter: (f64, f64),
+    ) -> Self {
+        Self {
+            photon_map: PhotonMap::trace(scene, photon_count, max_depth, shutter),
+            radius,
+            max_depth,
+        }
+    }
+
+    /// Density estimation of the light reflected along `r`.
+    fn photon_lighting(&self, r: &Ray, rec: &HitRecord, attenuation: Color3) -> Color3 {
+        let mat = rec.mat_ptr.clone().unwrap();
+        let mut sum = Color3::zero();
+        self.photon_map
+            .for_each_within(&rec.p, self.radius, &mut |photon| {
+                let cosine = rec.normal * photon.wi;
+                if photon.normal * rec.normal <= 0. || cosine <= 0. {
+                    return;
+                }
+                let scattered = Ray::new(rec.p, photon.wi, r.time());
+                // the BRDF, without the cosine `scattering` comes with
+                let brdf = mat.scattering(r, rec, attenuation, &scattered) / cosine;
+                sum += Vec3::elemul(brdf, photon.power);
+            });
+        sum / (PI * self.radius * self.radius)
+    }
+}
+
+impl Integrator for PhotonMapIntegrator {
+    fn li(
+        &self,
+        r: Ray,
+        scene: &Scene,
+        _camera: &dyn Camera,
+        _splats: &mut Vec<Splat>,
+    ) -> Radiance {
+        let mut radiance = Radiance::zero();
+        let mut throughput = Color3::ones();
+        let mut ray = r;
+
+        for _ in 0..self.max_depth {
+            let mut rec = HitRecord::new();
+            if !scene.world.hit(&ray, 0.000001, f64::INFINITY, &mut rec) {
+                radiance.accumulate(0, Vec3::elemul(throughput, scene.background));
+                break;
+            }
+
+            let mat = rec.mat_ptr.clone().unwrap();
+            let emitted = mat.emitted(&ray, &rec, rec.u, rec.v, &rec.p);
+            radiance.accumulate(0, Vec3::elemul(throughput, emitted));
+
+            let mut srec = ScatterRecord::new();
+            if !mat.scatter(&ray, &rec, &mut srec) {
+                break;
+            }
+            if srec.is_specular {
+                throughput = Vec3::elemul(throughput, srec.attenuation);
+                ray = srec.specular_ray;
+                continue;
+            }
+
+            let direct = delta_lighting(&ray, &rec, srec.attenuation, scene)
+                + area_lighting(&ray, &rec, srec.attenuation, scene);
+            radiance.accumulate(1, Vec3::elemul(throughput, direct));
+            let indirect = self.photon_lighting(&ray, &rec, srec.attenuation);
+            radiance.accumulate(2, Vec3::elemul(throughput, indirect));
+            break;
+        }
+
+        radiance
+    }
+}
+
+/// Progressive photon mapping, in the probabilistic form of Knaus and Zwicker
+/// (2011): every pass traces a new photon map and gathers with a smaller
+/// radius, so the average of the passes converges where one map stays biased.
+pub struct ProgressivePhotonMapping {
+    photons_per_pass: usize,
+    initial_radius: f64,
+    alpha: f64, // how much of the variance to trade for bias per pass
+    max_depth: usize,
+}
+
+impl ProgressivePhotonMapping {
+    pub fn new(photons_per_pass: usize, initial_radius: f64, max_depth: usize) -> Self {
+        Self {
+            photons_per_pass,
+            initial_radius,
+            alpha: 2. / 3.,
+            max_depth,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_alpha(mut self, alpha: f64) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// The gather radius of pass `pass`, counting from zero.
+    pub fn radius(&self, pass: usize) -> f64 {
+        let mut radius_squared = self.initial_radius * self.initial_radius;
+        for i in 1..=pass {
+            radius_squared *= (i as f64 + self.alpha) / (i as f64 + 1.);
+        }
+        radius_squared.sqrt()
+    }
+
+    /// Renders `passes` passes of `settings.samples_per_pixel` each, tracing
+    /// photons during the camera's `shutter` interval.
+    pub fn render(
+        &self,
+        cam: &Arc<dyn Camera + Send + Sync>,
+        scene: &Arc<Scene>,
+        settings: &RenderSettings,
+        passes: usize,
+        shutter: (f64, f64),
+    ) -> Film {
+        let mut film = Film::new(settings.width, settings.height, settings.filter.clone());
+        for pass in 0..passes {
+            let integrator: Arc<dyn Integrator + Send + Sync> = Arc::new(PhotonMapIntegrator {
+                photon_map: PhotonMap::trace(scene, self.photons_per_pass, self.max_depth, shutter),
+                radius: self.radius(pass),
+                max_depth: self.max_depth,
+            });
+            println!("Photon mapping pass {}/{}", pass + 1, passes);
+            film.merge(&render(cam, scene, &integrator, settings));
+        }
+        film
+    }
+}
+
+#[test]
+fn test_photon_map_search() {
+    let photons: Vec<Photon> = (0..1000)
+        .map(|_| Photon {
+            p: Vec3::random(-1., 1.),
+            normal: Vec3::new(0., 1., 0.),
+            wi: Vec3::new(0., 1., 0.),
+            power: Color3::ones(),
+        })
+        .collect();
+    let map = PhotonMap::new(photons.clone());
+    assert_eq!(map.photons.len(), 1000);
+
+    for _ in 0..20 {
+        let p = Vec3::random(-1., 1.);
+        let expected = photons
+            .iter()
+            .filter(|photon| (photon.p - p).squared_length() <= 0.09)
+            .count();
+        let mut found = 0;
+        map.for_each_within(&p, 0.3, &mut |_| found += 1);
+        assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    sync::{mpsc, Arc},
+    thread,
+};
+
+use indicatif::{MultiProgress, ProgressBar};
+use rand::random;
+
+use super::{
+    film::Film, integrator::Integrator, sanitize, scene::Scene, Radiance, RenderSettings,
+    THREAD_NUM,
+};
+use crate::{
+    camera::Camera,
+    color::luminance,
+    rtweekend::random_double_unit,
+    sampler::{self, metropolis::MetropolisSampler},
+};
+
+/// What one set of primary samples contributes to the film, and its
+/// importance, the luminance of all of it.
+struct Contribution {
+    splats: Vec<(f64, f64, Radiance)>,
+    importance: f64,
+}
+
+/// Primary sample space Metropolis light transport (Kelemen et al. 2002), as
+/// an alternative way of driving an integrator. Chains of mutated samples
+/// spend their time where the image is bright, which finds light through
+/// narrow openings that independent samples rarely do. The normalization
+/// comes from `bootstrap_samples` independent paths. No features are recorded.
+pub struct Metropolis {
+    mutations_per_pixel: usize,
+    bootstrap_samples: usize,
+    chains: usize,
+    sigma: f64,
+    large_step_probability: f64,
+}
+
+impl Metropolis {
+    pub fn new(mutations_per_pixel: usize) -> Self {
+        Self {
+            mutations_per_pixel,
+            bootstrap_samples: 100_000,
+            chains: 1000,
+            sigma: 0.01,
+            large_step_probability: 0.3,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_bootstrap(mut self, bootstrap_samples: usize, chains: usize) -> Self {
+        self.bootstrap_samples = bootstrap_samples;
+        self.chains = chains;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_mutations(mut self, sigma: f64, large_step_probability: f64) -> Self {
+        self.sigma = sigma;
+        self.large_step_probability = large_step_probability;
+        self
+    }
+
+    /// Follows the path the installed sampler describes, from a film position
+    /// taken from its first two numbers.
+    fn evaluate(
+        cam: &Arc<dyn Camera + Send + Sync>,
+        scene: &Scene,
+        integrator: &Arc<dyn Integrator + Send + Sync>,
+        clamp: Option<f64>,
+        film: &mut Film,
+    ) -> Contribution {
+        let width = film.width() as f64;
+        let height = film.height() as f64;
+        let x = random_double_unit() * width;
+        let y = random_double_unit() * height;
+        let i = (x as usize).min(film.width() - 1);
+        let j = (y as usize).min(film.height() - 1);
+
+        let mut splats = Vec::new();
+        let mut contribution = Contribution {
+            splats: Vec::new(),
+            importance: 0.,
+        };
+        if let Some(r) = cam.get_ray(x / width, y / height) {
+            let radiance = integrator.li(r, scene, cam.as_ref(), &mut splats);
+            if let Some(radiance) = sanitize(film, radiance, i, j, clamp) {
+                contribution.splats.push((x, y, radiance));
+            }
+            for splat in splats {
+                let (si, sj) = splat.pixel(film.width(), film.height());
+                if let Some(radiance) = sanitize(film, splat.radiance, si, sj, clamp) {
+                    contribution
+                        .splats
+                        .push((splat.s * width, splat.t * height, radiance));
+                }
+            }
+        }
+        contribution.importance = contribution
+            .splats
+            .iter()
+            .map(|(_, _, radiance)| luminance(radiance.total()))
+            .sum();
+        contribution
+    }
+
+    /// Renders one image with `mutations_per_pixel` mutations for each pixel.
+    pub fn render(
+        &self,
+        cam: &Arc<dyn Camera + Send + Sync>,
+        scene: &Arc<Scene>,
+        integrator: &Arc<dyn Integrator + Send + Sync>,
+        settings: &RenderSettings,
+    ) -> Film {
+        let width = settings.width;
+        let height = settings.height;
+        let mut film = Film::new(width, height, settings.filter.clone());
+
+        // Bootstrap: the importance of independent paths, to start the
+        // chains from and to know the brightness of the whole image by
+        let (tx, rx) = mpsc::channel();
+        for k in 0..THREAD_NUM {
+            let (cam, scene, integrator) = (cam.clone(), scene.clone(), integrator.clone());
+            let clamp = settings.clamp;
+            let bootstrap_samples = self.bootstrap_samples;
+            let (sigma, large_step_probability) = (self.sigma, self.large_step_probability);
+            let tx_k = tx.clone();
+            let mut film_t = film.clone();
+            thread::spawn(move || {
+                let mut weights = Vec::new();
+                for index in (k..bootstrap_samples).step_by(THREAD_NUM) {
+                    let seed = index as u64;
+                    sampler::install(Box::new(MetropolisSampler::new(
+                        seed,
+                        sigma,
+                        large_step_probability,
+                    )));
+                    let contribution =
+                        Self::evaluate(&cam, &scene, &integrator, clamp, &mut film_t);
+                    weights.push((index, contribution.importance));
+                }
+                tx_k.send((weights, film_t)).unwrap();
+            });
+        }
+        drop(tx);
+        let mut weights = vec![0.; self.bootstrap_samples];
+        for (received, film_t) in rx {
+            for (index, weight) in received {
+                weights[index] = weight;
+            }
+            film.merge(&film_t);
+        }
+        let total_weight: f64 = weights.iter().sum();
+        if total_weight <= 0. {
+            return film;
+        }
+        let b = total_weight / self.bootstrap_samples as f64;
+        let cdf: Vec<f64> = weights
+            .iter()
+            .scan(0., |sum, w| {
+                *sum += w / total_weight;
+                Some(*sum)
+            })
+            .collect();
+        let cdf = Arc::new(cdf);
+
+        // Chains, which share the mutations out between them
+        let pixel_count = width * height;
+        let total_mutations = self.mutations_per_pixel * pixel_count;
+        let bar_collection = MultiProgress::new();
+        let (tx, rx) = mpsc::channel();
+        for k in 0..THREAD_NUM {
+            let (cam, scene, integrator) = (cam.clone(), scene.clone(), integrator.clone());
+            let clamp = settings.clamp;
+            let cdf = cdf.clone();
+            let chains = self.chains;
+            let (sigma, large_step_probability) = (self.sigma, self.large_step_probability);
+            let tx_k = tx.clone();
+            let mut film_t = Film::new(width, height, settings.filter.clone());
+            let bar = bar_collection.add(if settings.hide_progress {
+                ProgressBar::hidden()
+            } else {
+                ProgressBar::new(((chains + THREAD_NUM - 1 - k) / THREAD_NUM) as u64)
+            });
+            thread::spawn(move || {
+                for chain in (k..chains).step_by(THREAD_NUM) {
+                    let mutations =
+                        total_mutations / chains + usize::from(chain < total_mutations % chains);
+
+                    // start where a bootstrap path was, chosen by importance
+                    let u: f64 = random();
+                    let index = cdf.partition_point(|&c| c < u).min(cdf.len() - 1);
+                    let sampler =
+                        MetropolisSampler::new(index as u64, sigma, large_step_probability);
+                    sampler::install(Box::new(sampler.clone()));
+                    let mut current = Self::evaluate(&cam, &scene, &integrator, clamp, &mut film_t);
+
+                    for _ in 0..mutations {
+                        sampler.start_iteration();
+                        let proposed =
+                            Self::evaluate(&cam, &scene, &integrator, clamp, &mut film_t);
+                        let accept = if current.importance > 0. {
+                            (proposed.importance / current.importance).min(1.)
+                        } else {
+                            1.
+                        };
+
+                        // both states are recorded, weighted by how likely each is kept
+                        if accept > 0. {
+                            for &(x, y, radiance) in &proposed.splats {
+                                film_t.add_splat(
+                                    x,
+                                    y,
+                                    radiance * (b * accept / proposed.importance),
+                                );
+                            }
+                        }
+                        if accept < 1. {
+                            for &(x, y, radiance) in &current.splats {
+                                film_t.add_splat(
+                                    x,
+                                    y,
+                                    radiance * (b * (1. - accept) / current.importance),
+                                );
+                            }
+                        }
+
+                        if random::<f64>() < accept {
+                            current = proposed;
+                            sampler.accept();
+                        } else {
+                            sampler.reject();
+                        }
+                    }
+                    bar.inc(1);
+                }
+                bar.finish();
+                tx_k.send(film_t).unwrap();
+            });
+        }
+        drop(tx);
+        for received in rx {
+            film.merge(&received);
+        }
+
+        // the splats hold the sum over all mutations
+        for i in 0..width {
+            for j in 0..height {
+                film.add_sample_count(i, j, self.mutations_per_pixel);
+            }
+        }
+        let invalid = film.invalid_samples();
+        if !invalid.is_empty() {
+            println!(
+                "Discarded {} NaN/Inf samples, e.g. at pixels {:?}",
+                film.invalid_sample_count(),
+                invalid
+            );
+        }
+        film
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use crate::{hittable::Hittable, light::LightList, Color3};
+
+/// Everything an integrator needs to know about what is being rendered.
+pub struct Scene {
+    pub world: Arc<dyn Hittable + Send + Sync>,
+    pub lights: LightList,
+    pub background: Color3,
+}
+
+impl Scene {
+    pub fn new(
+        world: Arc<dyn Hittable + Send + Sync>,
+        lights: LightList,
+        background: Color3,
+    ) -> Self {
+        Self {
+            world,
+            lights,
+            background,
+        }
+    }
+
+    /// The same lights and background around a different world, e.g. one
+    /// refit for the next frame.
+    pub fn with_world(&self, world: Arc<dyn Hittable + Send + Sync>) -> Self {
+        Self {
+            world,
+            lights: self.lights.clone(),
+            background: self.background,
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use std::f64::consts::PI;
 
+use crate::sampler;
+
 pub fn degrees_to_radius(degrees: f64) -> f64 {
     degrees * PI / 180.
 }
@@ -14,8 +16,15 @@ pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
     }
 }
 
+/// The next dimension of the installed sampler, or a pseudo-random number.
 pub fn random_double_unit() -> f64 {
-    rand::random::<f64>()
+    sampler::next_1d().unwrap_or_else(rand::random::<f64>)
+}
+
+/// Two numbers meant to be used together, e.g. a point on a disk, which lets
+/// the sampler stratify them jointly.
+pub fn random_double_2d() -> (f64, f64) {
+    sampler::next_2d().unwrap_or_else(|| (rand::random::<f64>(), rand::random::<f64>()))
 }
 
 pub fn random_double(min: f64, max: f64) -> f64 {
@@ -0,0 +1,122 @@
+pub mod halton;
+pub mod metropolis;
+pub mod sobol;
+pub mod stratified;
+
+use std::cell::RefCell;
+
+/// Source of the random numbers of one render thread. Every sample of a pixel
+/// asks for its numbers in the same order, so the n-th request of a sample is
+/// its n-th dimension: pixel jitter, lens, time, then one set per bounce.
+pub trait Sampler {
+    /// Begins the `index`-th sample of pixel (`x`, `y`), restarting at dimension zero.
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize);
+    fn get_1d(&mut self) -> f64;
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+    fn clone_box(&self) -> Box<dyn Sampler + Send>;
+}
+
+thread_local! {
+    static SAMPLER: RefCell<Option<Box<dyn Sampler + Send>>> = RefCell::new(None);
+}
+
+/// Makes `sampler` the source of `random_double_unit()` on the current thread.
+pub fn install(sampler: Box<dyn Sampler + Send>) {
+    SAMPLER.with(|s| *s.borrow_mut() = Some(sampler));
+}
+
+pub fn start_pixel_sample(x: usize, y: usize, index: usize) {
+    SAMPLER.with(|s| {
+        if let Some(sampler) = s.borrow_mut().as_mut() {
+            sampler.start_pixel_sample(x, y, index);
+        }
+    });
+}
+
+/// The next dimension of the installed sampler, if there is one.
+pub fn next_1d() -> Option<f64> {
+    SAMPLER.with(|s| s.borrow_mut().as_mut().map(|sampler| sampler.get_1d()))
+}
+
+pub fn next_2d() -> Option<(f64, f64)> {
+    SAMPLER.with(|s| s.borrow_mut().as_mut().map(|sampler| sampler.get_2d()))
+}
+
+/// Plain pseudo-random numbers, the behaviour without any sampler installed.
+#[derive(Clone)]
+pub struct IndependentSampler;
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, _x: usize, _y: usize, _index: usize) {}
+
+    fn get_1d(&mut self) -> f64 {
+        rand::random::<f64>()
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler + Send> {
+        Box::new(self.clone())
+    }
+}
+
+/// Scrambles the bits of `v` (the finalizer of SplitMix64).
+pub fn mix_bits(mut v: u64) -> u64 {
+    v ^= v >> 31;
+    v = v.wrapping_mul(0x7fb5d329728ea185);
+    v ^= v >> 27;
+    v = v.wrapping_mul(0x81dadef4bc2dd44d);
+    v ^= v >> 33;
+    v
+}
+
+/// A per pixel and dimension seed, so that neighbouring pixels are decorrelated.
+pub fn pixel_seed(x: usize, y: usize, dimension: usize) -> u64 {
+    mix_bits(((x as u64) << 40) ^ ((y as u64) << 20) ^ dimension as u64 ^ 0x9e3779b97f4a7c15)
+}
+
+/// The `i`-th element of a pseudo-random permutation of 0..`n` chosen by
+/// `seed` (Kensler, "Correlated Multi-Jittered Sampling").
+pub fn permutation_element(mut i: u32, n: u32, seed: u32) -> u32 {
+    let mut w = n - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < n {
+            break;
+        }
+    }
+    (i.wrapping_add(seed)) % n
+}
+
+#[test]
+fn test_permutation() {
+    for n in [1, 5, 16, 100] {
+        let mut seen = vec![false; n as usize];
+        for i in 0..n {
+            seen[permutation_element(i, n, 0xdeadbeef) as usize] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+}
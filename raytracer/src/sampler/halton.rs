@@ -0,0 +1,83 @@
+use rand::random;
+
+use super::{pixel_seed, Sampler};
+
+/// Dimensions beyond this fall back to pseudo-random numbers.
+const MAX_DIMENSION: usize = 128;
+
+/// The Halton sequence with one prime base per dimension. Every pixel walks
+/// the same sequence, shifted by a per pixel random offset (Cranley-Patterson
+/// rotation) to hide the correlation between neighbouring pixels.
+#[derive(Clone)]
+pub struct HaltonSampler {
+    primes: Vec<u64>,
+    pixel: (usize, usize),
+    index: u64,
+    dimension: usize,
+}
+
+impl HaltonSampler {
+    pub fn new() -> Self {
+        let mut primes = Vec::new();
+        let mut candidate = 2;
+        while primes.len() < MAX_DIMENSION {
+            if primes.iter().all(|p| candidate % p != 0) {
+                primes.push(candidate);
+            }
+            candidate += 1;
+        }
+        Self {
+            primes,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// The radical inverse of `index` in the given base.
+    fn radical_inverse(mut index: u64, base: u64) -> f64 {
+        let inv_base = 1. / base as f64;
+        let mut inv_base_n = 1.;
+        let mut reversed = 0.;
+        while index > 0 {
+            let digit = index % base;
+            inv_base_n *= inv_base;
+            reversed += digit as f64 * inv_base_n;
+            index /= base;
+        }
+        reversed
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.pixel = (x, y);
+        // skip the first point, which is zero in every dimension
+        self.index = index as u64 + 1;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        if dimension >= MAX_DIMENSION {
+            return random::<f64>();
+        }
+
+        let value = Self::radical_inverse(self.index, self.primes[dimension]);
+        let shift =
+            (pixel_seed(self.pixel.0, self.pixel.1, dimension) >> 11) as f64 / (1_u64 << 53) as f64;
+        (value + shift).fract()
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler + Send> {
+        Box::new(self.clone())
+    }
+}
+
+#[test]
+fn test_radical_inverse() {
+    assert_eq!(HaltonSampler::radical_inverse(1, 2), 0.5);
+    assert_eq!(HaltonSampler::radical_inverse(3, 2), 0.75);
+    assert!((HaltonSampler::radical_inverse(5, 3) - 7. / 9.).abs() < 1e-12);
+}
@@ -0,0 +1,162 @@
+use std::{
+    f64::consts::PI,
+    sync::{Arc, Mutex},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::Sampler;
+
+#[derive(Clone, Copy)]
+struct PrimarySample {
+    value: f64,
+    last_modified: usize,
+    // to undo a rejected mutation
+    backup: f64,
+    backup_last_modified: usize,
+}
+
+/// A point in primary sample space. Coordinates are mutated lazily, when a
+/// path asks for them, catching up on all the mutations they missed.
+struct PrimarySamples {
+    rng: StdRng,
+    x: Vec<PrimarySample>,
+    sigma: f64,
+    large_step_probability: f64,
+    iteration: usize,
+    large_step: bool,
+    last_large_step: usize,
+    dimension: usize,
+}
+
+impl PrimarySamples {
+    fn next(&mut self) -> f64 {
+        let i = self.dimension;
+        self.dimension += 1;
+        if i >= self.x.len() {
+            // a number never asked for before is as good as a fresh one, and a
+            // small step from nothing would keep rejection loops going forever
+            let value = self.rng.gen();
+            self.x.push(PrimarySample {
+                value,
+                last_modified: self.iteration,
+                backup: value,
+                backup_last_modified: self.iteration,
+            });
+            return value;
+        }
+
+        let xi = &mut self.x[i];
+        // large steps it missed replace it with a fresh number
+        if xi.last_modified < self.last_large_step {
+            xi.value = self.rng.gen();
+            xi.last_modified = self.last_large_step;
+        }
+        xi.backup = xi.value;
+        xi.backup_last_modified = xi.last_modified;
+        if self.large_step {
+            xi.value = self.rng.gen();
+        } else {
+            // the small steps it missed add up to one of sigma √n
+            let small_steps = (self.iteration - xi.last_modified) as f64;
+            let (u1, u2): (f64, f64) = (self.rng.gen(), self.rng.gen());
+            let normal = (-2. * (1. - u1).ln()).sqrt() * (2. * PI * u2).cos();
+            xi.value += normal * self.sigma * small_steps.sqrt();
+            xi.value -= xi.value.floor();
+        }
+        xi.last_modified = self.iteration;
+        xi.value
+    }
+}
+
+/// Replays and mutates the random numbers of a path for Metropolis light
+/// transport in primary sample space (Kelemen et al. 2002). Each mutation is
+/// either a large step, which draws all numbers anew, or a small one, which
+/// perturbs them by `sigma`. Clones share the same state, so a copy can be
+/// installed on the thread while another drives the chain.
+#[derive(Clone)]
+pub struct MetropolisSampler {
+    state: Arc<Mutex<PrimarySamples>>,
+}
+
+impl MetropolisSampler {
+    /// A sampler whose first iteration draws its numbers from `seed`.
+    pub fn new(seed: u64, sigma: f64, large_step_probability: f64) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(PrimarySamples {
+                rng: StdRng::seed_from_u64(seed),
+                x: Vec::new(),
+                sigma,
+                large_step_probability,
+                iteration: 0,
+                large_step: true,
+                last_large_step: 0,
+                dimension: 0,
+            })),
+        }
+    }
+
+    /// Begins proposing a mutation of the current numbers.
+    pub fn start_iteration(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.iteration += 1;
+        state.large_step = state.rng.gen::<f64>() < state.large_step_probability;
+        state.dimension = 0;
+    }
+
+    pub fn accept(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.large_step {
+            state.last_large_step = state.iteration;
+        }
+    }
+
+    /// Goes back to the numbers from before the last `start_iteration`.
+    pub fn reject(&self) {
+        let mut state = self.state.lock().unwrap();
+        let iteration = state.iteration;
+        for xi in state.x.iter_mut() {
+            if xi.last_modified == iteration {
+                xi.value = xi.backup;
+                xi.last_modified = xi.backup_last_modified;
+            }
+        }
+        state.iteration -= 1;
+    }
+}
+
+impl Sampler for MetropolisSampler {
+    // the chain picks its own pixels
+    fn start_pixel_sample(&mut self, _x: usize, _y: usize, _index: usize) {
+        self.state.lock().unwrap().dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.state.lock().unwrap().next()
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler + Send> {
+        Box::new(self.clone())
+    }
+}
+
+#[test]
+fn test_metropolis_reject() {
+    let mut sampler = MetropolisSampler::new(7, 0.01, 0.3);
+    let first: Vec<f64> = (0..8).map(|_| sampler.get_1d()).collect();
+
+    // a seed replays its numbers
+    let mut replay = MetropolisSampler::new(7, 0.01, 0.3);
+    assert_eq!((0..8).map(|_| replay.get_1d()).collect::<Vec<_>>(), first);
+
+    // a rejected mutation leaves the numbers as they were
+    for _ in 0..10 {
+        sampler.start_iteration();
+        let mutated: Vec<f64> = (0..8).map(|_| sampler.get_1d()).collect();
+        assert_ne!(mutated, first);
+        sampler.reject();
+    }
+    sampler.start_pixel_sample(0, 0, 0);
+    let state = sampler.state.lock().unwrap();
+    assert!(state.x.iter().zip(&first).all(|(xi, &v)| xi.value == v));
+}
@@ -0,0 +1,71 @@
+use rand::random;
+
+use super::{permutation_element, pixel_seed, Sampler};
+
+/// Jittered samples: each dimension is split into one stratum per sample (a
+/// grid for 2D requests), and the strata are shuffled independently per pixel
+/// and dimension so that the dimensions do not correlate with each other.
+#[derive(Clone)]
+pub struct StratifiedSampler {
+    samples_per_pixel: usize,
+    x_strata: usize,
+    y_strata: usize,
+    pixel: (usize, usize),
+    index: usize,
+    dimension: usize,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: usize) -> Self {
+        let x_strata = ((samples_per_pixel as f64).sqrt() as usize).max(1);
+        Self {
+            samples_per_pixel: samples_per_pixel.max(1),
+            x_strata,
+            y_strata: (samples_per_pixel / x_strata).max(1),
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    fn stratum(&mut self, n: usize) -> Option<usize> {
+        let seed = pixel_seed(self.pixel.0, self.pixel.1, self.dimension) as u32;
+        self.dimension += 1;
+        if self.index >= n {
+            return None;
+        }
+        Some(permutation_element(self.index as u32, n as u32, seed) as usize)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.pixel = (x, y);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        match self.stratum(self.samples_per_pixel) {
+            Some(stratum) => (stratum as f64 + random::<f64>()) / self.samples_per_pixel as f64,
+            None => random::<f64>(),
+        }
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let stratum = self.stratum(self.x_strata * self.y_strata);
+        // a 2D request uses up two dimensions
+        self.dimension += 1;
+        match stratum {
+            Some(stratum) => (
+                ((stratum % self.x_strata) as f64 + random::<f64>()) / self.x_strata as f64,
+                ((stratum / self.x_strata) as f64 + random::<f64>()) / self.y_strata as f64,
+            ),
+            None => (random::<f64>(), random::<f64>()),
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler + Send> {
+        Box::new(self.clone())
+    }
+}
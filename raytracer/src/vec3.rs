@@ -2,7 +2,9 @@ use std::ops::{
     Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
 };
 
-use crate::rtweekend::{random_double, random_double_unit};
+use std::f64::consts::PI;
+
+use crate::rtweekend::{random_double, random_double_2d, random_double_unit};
 
 #[derive(Clone, Debug, PartialEq, Copy)]
 pub struct Vec3 {
@@ -120,13 +122,18 @@ impl Vec3 {
     }
 
     pub fn random_in_unit_disk() -> Self {
-        loop {
-            let p = Vec3::new(random_double(-1., 1.), random_double(-1., 1.), 0.);
-            if p.squared_length() >= 1. {
-                continue;
-            }
-            return p;
+        // concentric mapping, which keeps stratified samples stratified
+        let (u1, u2) = random_double_2d();
+        let (x, y) = (2. * u1 - 1., 2. * u2 - 1.);
+        if x == 0. && y == 0. {
+            return Self::zero();
         }
+        let (r, theta) = if x.abs() > y.abs() {
+            (x, PI / 4. * (y / x))
+        } else {
+            (y, PI / 2. - PI / 4. * (x / y))
+        };
+        Vec3::new(r * theta.cos(), r * theta.sin(), 0.)
     }
 }
 